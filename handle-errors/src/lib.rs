@@ -1,18 +1,26 @@
 use warp::{
-    Rejection, 
+    Filter,
+    Rejection,
     Reply,
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     reject::Reject,
     filters::body::BodyDeserializeError,
     filters::cors::CorsForbidden,
+    reject::{InvalidQuery, MethodNotAllowed, MissingHeader, PayloadTooLarge, UnsupportedMediaType},
 };
 
+use serde::Serialize;
 use tracing::{event, Level, instrument};
-//use sqlx::error::Error as SqlxError;
+use sqlx::error::{Error as SqlxError, ErrorKind as SqlxErrorKind};
 use reqwest::Error as ReqwestError;
+use argon2::Error as ArgonError;
 
+use std::convert::Infallible;
 use std::fmt;
 
+/// Header carrying the id of a request, in both directions
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
 //#[derive(Debug)]
 //struct InvalidId;
 //
@@ -22,10 +30,151 @@ use std::fmt;
 pub enum Error {
     ParseError(std::num::ParseIntError),
     MissingParameters,
-    DatabaseQueryError,
+    DatabaseQueryError(DatabaseError),
     ExternalAPIError(ReqwestError),
     ClientError(APILayerError),
     ServerError(APILayerError),
+    ValidationError(Vec<FieldError>),
+    /// Content rejected by the profanity policy
+    ProfaneContent(Vec<ProfaneWord>),
+    WrongPassword,
+    ArgonLibraryError(ArgonError),
+    CannotDecryptToken,
+    Unauthorized,
+    Forbidden,
+    NotFound { resource: Resource, id: i32 },
+    /// Lookup of a resource addressed by name instead of id, like a tag
+    NotFoundByName { resource: Resource, name: String },
+}
+
+/// Kind of a resource which can be looked up by id or name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    Question,
+    Answer,
+    Revision,
+    Comment,
+    Tag,
+    TagSynonym,
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Resource::Question => write!(f, "Question"),
+            Resource::Answer => write!(f, "Answer"),
+            Resource::Revision => write!(f, "Revision"),
+            Resource::Comment => write!(f, "Comment"),
+            Resource::Tag => write!(f, "Tag"),
+            Resource::TagSynonym => write!(f, "Tag synonym"),
+        }
+    }
+}
+
+/// A failed database operation together with what went wrong
+#[derive(Debug)]
+pub struct DatabaseError {
+    pub kind: DatabaseErrorKind,
+    /// Name of the violated constraint, e.g. `users_email_key`
+    pub constraint: Option<String>,
+    /// The original error, `None` for backends which are not using sqlx
+    pub source: Option<SqlxError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatabaseErrorKind {
+    UniqueViolation,
+    ForeignKeyViolation,
+    CheckViolation,
+    NotNullViolation,
+    /// No connection could be acquired or the database is unreachable
+    Unavailable,
+    Other,
+}
+
+impl DatabaseError {
+    /// Creates an error for a violated constraint without an sqlx source
+    pub fn constraint(kind: DatabaseErrorKind, constraint: &str) -> Self {
+        DatabaseError {
+            kind,
+            constraint: Some(constraint.to_string()),
+            source: None,
+        }
+    }
+}
+
+impl From<SqlxError> for DatabaseError {
+    fn from(err: SqlxError) -> Self {
+        let (kind, constraint) = match err {
+            SqlxError::Database(ref e) => {
+                let kind = match e.kind() {
+                    SqlxErrorKind::UniqueViolation => DatabaseErrorKind::UniqueViolation,
+                    SqlxErrorKind::ForeignKeyViolation => DatabaseErrorKind::ForeignKeyViolation,
+                    SqlxErrorKind::CheckViolation => DatabaseErrorKind::CheckViolation,
+                    SqlxErrorKind::NotNullViolation => DatabaseErrorKind::NotNullViolation,
+                    _ => DatabaseErrorKind::Other,
+                };
+                (kind, e.constraint().map(str::to_string))
+            }
+            SqlxError::PoolTimedOut | SqlxError::PoolClosed | SqlxError::Io(_) => {
+                (DatabaseErrorKind::Unavailable, None)
+            }
+            _ => (DatabaseErrorKind::Other, None),
+        };
+
+        DatabaseError {
+            kind,
+            constraint,
+            source: Some(err),
+        }
+    }
+}
+
+impl fmt::Display for DatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self.kind {
+            DatabaseErrorKind::UniqueViolation => write!(f, "Conflicts with an existing record")?,
+            DatabaseErrorKind::ForeignKeyViolation => {
+                write!(f, "References a missing record or is still referenced")?
+            }
+            DatabaseErrorKind::CheckViolation | DatabaseErrorKind::NotNullViolation => {
+                write!(f, "Violates a database constraint")?
+            }
+            DatabaseErrorKind::Unavailable => write!(f, "Database is unavailable")?,
+            DatabaseErrorKind::Other => write!(f, "Query could not be executed")?,
+        }
+
+        match self.constraint {
+            Some(ref constraint) => write!(f, " ({})", constraint),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A single invalid field of a request
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: &str) -> Self {
+        FieldError {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// An offending word found in a field of a request
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfaneWord {
+    pub field: String,
+    pub word: String,
+    /// Character offsets in the field, `end` excluded
+    pub start: usize,
+    pub end: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -45,10 +194,83 @@ impl fmt::Display for Error {
         match *self {
             Error::ParseError(ref err) => write!(f, "cannot parse parameter: {}", err),
             Error::MissingParameters => write!(f, "missing parameter"),
-	    Error::DatabaseQueryError => write!(f, "Query could not be executed"),
-	    Error::ExternalAPIError(ref err) => write!(f, "Cannot execute: {}", err), 
+	    Error::DatabaseQueryError(ref err) => write!(f, "{}", err),
+	    Error::ExternalAPIError(ref err) => write!(f, "Cannot execute: {}", err),
 	    Error::ClientError(ref err) => write!(f, "External Client error: {}", err),
 	    Error::ServerError(ref err) => write!(f, "External Server error: {}", err),
+	    Error::ValidationError(ref errors) => {
+		let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+		write!(f, "Invalid input in: {}", fields.join(", "))
+	    }
+	    Error::ProfaneContent(ref words) => {
+		write!(f, "Contains {} offending word(s)", words.len())
+	    }
+	    Error::WrongPassword => write!(f, "Wrong password"),
+	    Error::ArgonLibraryError(ref err) => write!(f, "Cannot verify password: {}", err),
+	    Error::CannotDecryptToken => write!(f, "Cannot decrypt token"),
+	    Error::Unauthorized => write!(f, "No credentials provided"),
+	    Error::Forbidden => write!(f, "Not allowed to access this resource"),
+	    Error::NotFound { resource, id } => write!(f, "{} {} not found", resource, id),
+	    Error::NotFoundByName { resource, ref name } => write!(f, "{} {} not found", resource, name),
+        }
+    }
+}
+
+impl Error {
+    /// HTTP status the error is answered with
+    pub fn status(&self) -> StatusCode {
+        match *self {
+            Error::ParseError(_) => StatusCode::BAD_REQUEST,
+            Error::MissingParameters => StatusCode::BAD_REQUEST,
+            Error::ValidationError(_) => StatusCode::BAD_REQUEST,
+            Error::ProfaneContent(_) => StatusCode::BAD_REQUEST,
+            Error::WrongPassword => StatusCode::UNAUTHORIZED,
+            Error::CannotDecryptToken => StatusCode::UNAUTHORIZED,
+            Error::Unauthorized => StatusCode::UNAUTHORIZED,
+            Error::Forbidden => StatusCode::FORBIDDEN,
+            Error::NotFound { .. } => StatusCode::NOT_FOUND,
+            Error::NotFoundByName { .. } => StatusCode::NOT_FOUND,
+            Error::DatabaseQueryError(ref err) => match err.kind {
+                DatabaseErrorKind::UniqueViolation => StatusCode::CONFLICT,
+                DatabaseErrorKind::ForeignKeyViolation => StatusCode::BAD_REQUEST,
+                DatabaseErrorKind::CheckViolation => StatusCode::BAD_REQUEST,
+                DatabaseErrorKind::NotNullViolation => StatusCode::BAD_REQUEST,
+                DatabaseErrorKind::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+                DatabaseErrorKind::Other => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            Error::ArgonLibraryError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            // Our request to the moderation API was rejected, that is our fault
+            Error::ClientError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::ExternalAPIError(_) => StatusCode::BAD_GATEWAY,
+            Error::ServerError(_) => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    /// Stable, machine readable identifier of the error
+    pub fn code(&self) -> &'static str {
+        match *self {
+            Error::ParseError(_) => "invalid_parameter",
+            Error::MissingParameters => "missing_parameter",
+            Error::ValidationError(_) => "validation_failed",
+            Error::ProfaneContent(_) => "profane_content",
+            Error::WrongPassword => "invalid_credentials",
+            Error::CannotDecryptToken => "invalid_token",
+            Error::Unauthorized => "unauthorized",
+            Error::Forbidden => "forbidden",
+            Error::NotFound { .. } => "not_found",
+            Error::NotFoundByName { .. } => "not_found",
+            Error::DatabaseQueryError(ref err) => match err.kind {
+                DatabaseErrorKind::UniqueViolation => "conflict",
+                DatabaseErrorKind::ForeignKeyViolation => "invalid_reference",
+                DatabaseErrorKind::CheckViolation => "constraint_violation",
+                DatabaseErrorKind::NotNullViolation => "constraint_violation",
+                DatabaseErrorKind::Unavailable => "database_unavailable",
+                DatabaseErrorKind::Other => "database_error",
+            },
+            Error::ArgonLibraryError(_) => "internal_error",
+            Error::ClientError(_) => "moderation_request_failed",
+            Error::ExternalAPIError(_) => "moderation_unavailable",
+            Error::ServerError(_) => "moderation_unavailable",
         }
     }
 }
@@ -56,70 +278,131 @@ impl fmt::Display for Error {
 impl Reject for Error {}
 impl Reject for APILayerError {}
 
-//impl From<SqlxError> for Error {
-//    fn from(err: SqlxError) -> Self {
-//	Error::DatabaseQueryError(err)
-//    }
-//}
+impl From<SqlxError> for Error {
+    fn from(err: SqlxError) -> Self {
+	Error::DatabaseQueryError(err.into())
+    }
+}
+
+/// Error body following RFC 7807 (`application/problem+json`)
+#[derive(Debug, Serialize)]
+struct Problem {
+    #[serde(rename = "type")]
+    problem_type: &'static str,
+    title: &'static str,
+    status: u16,
+    detail: String,
+    code: &'static str,
+    request_id: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<FieldError>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    bad_words: Vec<ProfaneWord>,
+}
+
+impl Problem {
+    fn new(status: StatusCode, code: &'static str, detail: String, request_id: &str) -> Self {
+        Problem {
+            problem_type: "about:blank",
+            title: status.canonical_reason().unwrap_or("Unknown"),
+            status: status.as_u16(),
+            detail,
+            code,
+            request_id: request_id.to_string(),
+            errors: Vec::new(),
+            bad_words: Vec::new(),
+        }
+    }
+
+    fn into_reply(self) -> impl Reply {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
 
+        let reply = warp::reply::with_status(warp::reply::json(&self), status);
+        warp::reply::with_header(reply, "content-type", "application/problem+json")
+    }
+}
+
+/// The id the client sent in the `x-request-id` header, or a new one.
+/// Ids which are too long or not printable are replaced, they end up in the logs
+pub fn request_id() -> impl Filter<Extract = (String,), Error = Infallible> + Clone {
+    warp::header::headers_cloned().map(|headers: HeaderMap| {
+        headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|id| id.to_str().ok())
+            .filter(|id| !id.is_empty() && id.len() <= 64)
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+    })
+}
+
+/// Turns a rejection into a problem, the request id from the `request_id` filter
+/// is sent back and logged, so a report can be matched with the logs
 #[instrument]
-pub async fn return_error(r: Rejection) -> Result<impl Reply, Rejection> {
-    if let Some(Error::DatabaseQueryError) = r.find() {
-        event!(Level::ERROR, "Database query error");
-
-        Ok(warp::reply::with_status(
-            Error::DatabaseQueryError.to_string(),
-            StatusCode::UNPROCESSABLE_ENTITY,
-        ))
-    } else if let Some(Error::ExternalAPIError(err)) = r.find() {
-	event!(Level::ERROR, "{}", err);
-
-	Ok(warp::reply::with_status(
-	    "Internal Server Error".to_string(),
-	    StatusCode::INTERNAL_SERVER_ERROR,
-	))
-    } else if let Some(Error::ClientError(err)) = r.find() {
-	event!(Level::ERROR, "{}", err);
-
-	Ok(warp::reply::with_status(
-	    "Internal Server Error".to_string(),
-	    StatusCode::INTERNAL_SERVER_ERROR,
-	))
-    } else if let Some(Error::ServerError(err)) = r.find() {
-	event!(Level::ERROR, "{}", err);
-
-	Ok(warp::reply::with_status(
-	    "Internal Server Error".to_string(),
-	    StatusCode::INTERNAL_SERVER_ERROR,
-	))
-    } else if let Some(err) = r.find::<Error>() {
-    //if let Some(err) = r.find::<Error>() {
-	event!(Level::ERROR, "{}", err);
-
-        Ok(warp::reply::with_status(
-            err.to_string(),
-            StatusCode::RANGE_NOT_SATISFIABLE,
-        ))
+pub async fn return_error(request_id: String, r: Rejection) -> Result<impl Reply, Rejection> {
+    let problem = if let Some(err) = r.find::<Error>() {
+        let status = err.status();
+
+        // Internals of server side failures stay in the logs
+        let detail = if status.is_server_error() {
+            event!(Level::ERROR, request_id = %request_id, "{}", err);
+            status.canonical_reason().unwrap_or("Internal Server Error").to_string()
+        } else {
+            event!(Level::WARN, request_id = %request_id, "{}", err);
+            err.to_string()
+        };
+
+        let mut problem = Problem::new(status, err.code(), detail, &request_id);
+        match *err {
+            Error::ValidationError(ref errors) => problem.errors = errors.clone(),
+            Error::ProfaneContent(ref words) => problem.bad_words = words.clone(),
+            _ => (),
+        }
+        problem
     } else if let Some(err) = r.find::<CorsForbidden>() {
-	event!(Level::ERROR, "CORS forbidden error: {}", err);	
+	event!(Level::ERROR, request_id = %request_id, "CORS forbidden error: {}", err);
 
-        Ok(warp::reply::with_status(
-            err.to_string(),
-            StatusCode::FORBIDDEN,
-        ))
+        Problem::new(StatusCode::FORBIDDEN, "cors_forbidden", err.to_string(), &request_id)
     } else if let Some(err) = r.find::<BodyDeserializeError>() {
-	event!(Level::ERROR, "Cannot deserialize request body: {}", err);
+	event!(Level::ERROR, request_id = %request_id, "Cannot deserialize request body: {}", err);
+
+        Problem::new(StatusCode::BAD_REQUEST, "invalid_body", err.to_string(), &request_id)
+    } else if let Some(err) = r.find::<InvalidQuery>() {
+	event!(Level::ERROR, request_id = %request_id, "{}", err);
+
+        Problem::new(StatusCode::BAD_REQUEST, "invalid_query", err.to_string(), &request_id)
+    } else if let Some(err) = r.find::<MissingHeader>() {
+	event!(Level::ERROR, request_id = %request_id, "{}", err);
+
+        Problem::new(StatusCode::BAD_REQUEST, "missing_header", err.to_string(), &request_id)
+    } else if let Some(err) = r.find::<PayloadTooLarge>() {
+	event!(Level::ERROR, request_id = %request_id, "{}", err);
 
-        Ok(warp::reply::with_status(
+        Problem::new(StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", err.to_string(), &request_id)
+    } else if let Some(err) = r.find::<UnsupportedMediaType>() {
+	event!(Level::ERROR, request_id = %request_id, "{}", err);
+
+        Problem::new(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "unsupported_media_type",
             err.to_string(),
-            StatusCode::UNPROCESSABLE_ENTITY,
-        ))
+            &request_id,
+        )
+    } else if r.is_not_found() || r.find::<MethodNotAllowed>().is_some() {
+	// Routes match on the method first, so warp reports unknown
+	// paths as "method not allowed" as soon as one route has another method
+	event!(Level::WARN, request_id = %request_id, "Requested route not found");
+
+        Problem::new(StatusCode::NOT_FOUND, "route_not_found", "Route not found".to_string(), &request_id)
     } else {
-	event!(Level::WARN, "Requested route not found");
+	event!(Level::ERROR, request_id = %request_id, "Unhandled rejection: {:?}", r);
 
-        Ok(warp::reply::with_status(
-            "Route not found".to_string(),
-            StatusCode::NOT_FOUND,
-        ))
-    }
+        Problem::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            "Internal Server Error".to_string(),
+            &request_id,
+        )
+    };
+
+    Ok(problem.into_reply())
 }
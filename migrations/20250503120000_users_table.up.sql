@@ -0,0 +1,7 @@
+-- Add up migration script here
+create table if not exists users (
+  id serial primary key,
+  email varchar(255) not null unique,
+  password varchar(255) not null,
+  created_on timestamp not null default now()
+);
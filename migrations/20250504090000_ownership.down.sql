@@ -0,0 +1,4 @@
+-- Add down migration script here
+alter table answers drop column if exists account_id;
+alter table questions drop column if exists account_id;
+alter table users drop column if exists role;
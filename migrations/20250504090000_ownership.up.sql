@@ -0,0 +1,9 @@
+-- Add up migration script here
+alter table users
+  add column role varchar(16) not null default 'user'
+  check (role in ('user', 'moderator'));
+
+-- Rows created before accounts existed stay without an author
+-- and can only be changed by moderators
+alter table questions add column account_id integer references users(id);
+alter table answers add column account_id integer references users(id);
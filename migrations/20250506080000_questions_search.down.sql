@@ -0,0 +1,7 @@
+-- Add down migration script here
+drop trigger if exists answers_search_update on answers;
+drop function if exists answers_search_refresh();
+drop trigger if exists questions_search_update on questions;
+drop function if exists questions_search_refresh();
+drop index if exists questions_search_idx;
+alter table questions drop column if exists search;
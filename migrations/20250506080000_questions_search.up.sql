@@ -0,0 +1,40 @@
+-- Add up migration script here
+alter table questions add column search tsvector;
+
+-- Title ranks above the question body, which ranks above the answers
+create or replace function questions_search_refresh() returns trigger as $$
+begin
+  new.search :=
+    setweight(to_tsvector('english', coalesce(new.title, '')), 'A') ||
+    setweight(to_tsvector('english', coalesce(new.content, '')), 'B') ||
+    setweight(to_tsvector('english', coalesce(
+      (select string_agg(content, ' ') from answers where question_id = new.id), ''
+    )), 'C');
+  return new;
+end
+$$ language plpgsql;
+
+create trigger questions_search_update
+  before insert or update of title, content on questions
+  for each row execute function questions_search_refresh();
+
+-- Touching the title of the question recomputes its vector
+create or replace function answers_search_refresh() returns trigger as $$
+begin
+  if tg_op in ('UPDATE', 'DELETE') then
+    update questions set title = title where id = old.question_id;
+  end if;
+  if tg_op in ('INSERT', 'UPDATE') then
+    update questions set title = title where id = new.question_id;
+  end if;
+  return null;
+end
+$$ language plpgsql;
+
+create trigger answers_search_update
+  after insert or update of content, question_id or delete on answers
+  for each row execute function answers_search_refresh();
+
+update questions set title = title;
+
+create index questions_search_idx on questions using gin (search);
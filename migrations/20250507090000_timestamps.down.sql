@@ -0,0 +1,6 @@
+-- Add down migration script here
+alter table answers drop column if exists updated_on;
+alter table questions drop column if exists updated_on;
+
+alter table answers alter column created_on type timestamp using created_on at time zone 'UTC';
+alter table questions alter column created_on type timestamp using created_on at time zone 'UTC';
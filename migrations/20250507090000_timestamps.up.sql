@@ -0,0 +1,12 @@
+-- Add up migration script here
+-- Existing timestamps were written by `now()` of a UTC server
+alter table questions alter column created_on type timestamptz using created_on at time zone 'UTC';
+alter table answers alter column created_on type timestamptz using created_on at time zone 'UTC';
+
+alter table questions add column updated_on timestamptz;
+update questions set updated_on = created_on;
+alter table questions alter column updated_on set not null, alter column updated_on set default now();
+
+alter table answers add column updated_on timestamptz;
+update answers set updated_on = created_on;
+alter table answers alter column updated_on set not null, alter column updated_on set default now();
@@ -0,0 +1,3 @@
+-- Add down migration script here
+drop table if exists answer_revisions;
+drop table if exists question_revisions;
@@ -0,0 +1,30 @@
+-- Add up migration script here
+-- Every version of a question, the first one included
+create table if not exists question_revisions (
+  id serial primary key,
+  question_id integer not null references questions(id) on delete cascade,
+  revision integer not null,
+  title varchar(255) not null,
+  content text not null,
+  tags text[],
+  account_id integer references users(id),
+  created_on timestamptz not null default now(),
+  unique (question_id, revision)
+);
+
+create table if not exists answer_revisions (
+  id serial primary key,
+  answer_id integer not null references answers(id) on delete cascade,
+  revision integer not null,
+  content text not null,
+  account_id integer references users(id),
+  created_on timestamptz not null default now(),
+  unique (answer_id, revision)
+);
+
+-- The current state becomes the first revision of what already exists
+insert into question_revisions (question_id, revision, title, content, tags, account_id, created_on)
+  select id, 1, title, content, tags, account_id, updated_on from questions;
+
+insert into answer_revisions (answer_id, revision, content, account_id, created_on)
+  select id, 1, content, account_id, updated_on from answers;
@@ -0,0 +1,20 @@
+-- Add down migration script here
+drop trigger if exists answers_search_update on answers;
+create trigger answers_search_update
+  after insert or update of content, question_id or delete on answers
+  for each row execute function answers_search_refresh();
+
+create or replace function questions_search_refresh() returns trigger as $$
+begin
+  new.search :=
+    setweight(to_tsvector('english', coalesce(new.title, '')), 'A') ||
+    setweight(to_tsvector('english', coalesce(new.content, '')), 'B') ||
+    setweight(to_tsvector('english', coalesce(
+      (select string_agg(content, ' ') from answers where question_id = new.id), ''
+    )), 'C');
+  return new;
+end
+$$ language plpgsql;
+
+alter table answers drop column if exists deleted_at;
+alter table questions drop column if exists deleted_at;
@@ -0,0 +1,22 @@
+-- Add up migration script here
+alter table questions add column deleted_at timestamptz;
+alter table answers add column deleted_at timestamptz;
+
+-- Deleted answers no longer count towards the search vector of their question
+create or replace function questions_search_refresh() returns trigger as $$
+begin
+  new.search :=
+    setweight(to_tsvector('english', coalesce(new.title, '')), 'A') ||
+    setweight(to_tsvector('english', coalesce(new.content, '')), 'B') ||
+    setweight(to_tsvector('english', coalesce(
+      (select string_agg(content, ' ') from answers
+        where question_id = new.id and deleted_at is null), ''
+    )), 'C');
+  return new;
+end
+$$ language plpgsql;
+
+drop trigger if exists answers_search_update on answers;
+create trigger answers_search_update
+  after insert or update of content, question_id, deleted_at or delete on answers
+  for each row execute function answers_search_refresh();
@@ -0,0 +1,6 @@
+-- Add down migration script here
+drop trigger if exists votes_score_update on votes;
+drop function if exists votes_score_refresh();
+alter table answers drop column if exists score;
+alter table questions drop column if exists score;
+drop table if exists votes;
@@ -0,0 +1,38 @@
+-- Add up migration script here
+-- One vote per account and post, either on a question or on an answer
+create table if not exists votes (
+  id serial primary key,
+  account_id integer not null references users(id) on delete cascade,
+  question_id integer references questions(id) on delete cascade,
+  answer_id integer references answers(id) on delete cascade,
+  value smallint not null check (value in (-1, 1)),
+  created_on timestamptz not null default now(),
+  check ((question_id is null) <> (answer_id is null))
+);
+
+create unique index votes_question_account_key on votes (question_id, account_id)
+  where question_id is not null;
+create unique index votes_answer_account_key on votes (answer_id, account_id)
+  where answer_id is not null;
+
+alter table questions add column score integer not null default 0;
+alter table answers add column score integer not null default 0;
+
+-- Keeps the score of the voted post in step with its votes
+create or replace function votes_score_refresh() returns trigger as $$
+begin
+  if tg_op in ('UPDATE', 'DELETE') then
+    update questions set score = score - old.value where id = old.question_id;
+    update answers set score = score - old.value where id = old.answer_id;
+  end if;
+  if tg_op in ('INSERT', 'UPDATE') then
+    update questions set score = score + new.value where id = new.question_id;
+    update answers set score = score + new.value where id = new.answer_id;
+  end if;
+  return null;
+end
+$$ language plpgsql;
+
+create trigger votes_score_update
+  after insert or update or delete on votes
+  for each row execute function votes_score_refresh();
@@ -0,0 +1,3 @@
+-- Add up migration script here
+alter table questions
+  add column accepted_answer_id integer references answers(id) on delete set null;
@@ -0,0 +1,14 @@
+-- Add up migration script here
+-- Short remarks under a question or an answer
+create table if not exists comments (
+  id serial primary key,
+  content text not null,
+  question_id integer references questions(id) on delete cascade,
+  answer_id integer references answers(id) on delete cascade,
+  account_id integer not null references users(id) on delete cascade,
+  created_on timestamptz not null default now(),
+  check ((question_id is null) <> (answer_id is null))
+);
+
+create index comments_question_id_idx on comments (question_id) where question_id is not null;
+create index comments_answer_id_idx on comments (answer_id) where answer_id is not null;
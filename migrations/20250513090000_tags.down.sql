@@ -0,0 +1,5 @@
+-- Add down migration script here
+drop trigger if exists question_tags_update on questions;
+drop function if exists question_tags_refresh();
+drop table if exists question_tags;
+drop table if exists tags;
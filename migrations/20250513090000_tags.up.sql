@@ -0,0 +1,54 @@
+-- Add up migration script here
+-- Canonical list of tags, `questions.tags` stays the source of truth
+-- for filtering and is mirrored into `question_tags` by a trigger
+create table if not exists tags (
+  id serial primary key,
+  name text not null unique,
+  description text,
+  created_on timestamptz not null default now()
+);
+
+create table if not exists question_tags (
+  question_id integer not null references questions(id) on delete cascade,
+  tag_id integer not null references tags(id) on delete cascade,
+  primary key (question_id, tag_id)
+);
+
+create index question_tags_tag_id_idx on question_tags (tag_id);
+
+create or replace function question_tags_refresh() returns trigger as $$
+begin
+  insert into tags (name)
+    select distinct unnest(new.tags)
+    on conflict (name) do nothing;
+  delete from question_tags where question_id = new.id;
+  insert into question_tags (question_id, tag_id)
+    select new.id, id from tags where name = any(new.tags);
+  return null;
+end
+$$ language plpgsql;
+
+create trigger question_tags_update
+  after insert or update of tags on questions
+  for each row execute function question_tags_refresh();
+
+-- Existing tags get the slugs `normalize_tags` makes: tags the API would
+-- reject for their characters or length are dropped, duplicates as well,
+-- and only the first 5 are kept. This also fills the tables above through the trigger
+update questions q set tags = coalesce((
+  select array_agg(slug order by position)
+  from (
+    select slug, min(position) as position
+    from (
+      select trim(both '-' from regexp_replace(lower(tag), '[\s_-]+', '-', 'g')) as slug,
+        position
+      from unnest(q.tags) with ordinality as t(tag, position)
+      where lower(tag) !~ '[^a-z0-9+.\s_-]'
+    ) slugs
+    where slug <> '' and char_length(slug) <= 35
+    group by slug
+    order by position
+    limit 5
+  ) unique_slugs
+), '{}')
+where tags is not null;
@@ -0,0 +1,3 @@
+-- Add down migration script here
+drop table if exists tag_merges;
+drop table if exists tag_synonyms;
@@ -0,0 +1,20 @@
+-- Add up migration script here
+-- Aliases replaced by their tag whenever a question is written
+create table if not exists tag_synonyms (
+  alias text primary key,
+  tag_id integer not null references tags(id) on delete cascade,
+  account_id integer references users(id) on delete set null,
+  created_on timestamptz not null default now()
+);
+
+create index tag_synonyms_tag_id_idx on tag_synonyms (tag_id);
+
+-- Audit trail of merged tags and the questions each merge rewrote
+create table if not exists tag_merges (
+  id serial primary key,
+  source text not null,
+  target text not null,
+  account_id integer references users(id) on delete set null,
+  question_ids integer[] not null,
+  created_on timestamptz not null default now()
+);
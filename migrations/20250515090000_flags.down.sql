@@ -0,0 +1,5 @@
+-- Add down migration script here
+drop table if exists moderation_actions;
+drop table if exists flags;
+alter table answers drop column if exists hidden_at;
+alter table questions drop column if exists hidden_at;
@@ -0,0 +1,35 @@
+-- Add up migration script here
+-- Hidden posts are deleted as well, only moderators can bring them back
+alter table questions add column hidden_at timestamptz;
+alter table answers add column hidden_at timestamptz;
+
+-- Reports of questions and answers to the moderators
+create table if not exists flags (
+  id serial primary key,
+  question_id integer references questions(id) on delete cascade,
+  answer_id integer references answers(id) on delete cascade,
+  account_id integer references users(id) on delete set null,
+  reason text not null check (reason in ('spam', 'offensive', 'duplicate', 'off-topic')),
+  details text,
+  created_on timestamptz not null default now(),
+  resolved_on timestamptz,
+  check ((question_id is null) <> (answer_id is null))
+);
+
+-- One open flag per account and post
+create unique index flags_open_question_idx on flags (question_id, account_id)
+  where question_id is not null and resolved_on is null;
+create unique index flags_open_answer_idx on flags (answer_id, account_id)
+  where answer_id is not null and resolved_on is null;
+
+-- Audit trail of moderator actions, outlives the posts it mentions
+create table if not exists moderation_actions (
+  id serial primary key,
+  question_id integer,
+  answer_id integer,
+  account_id integer references users(id) on delete set null,
+  action text not null check (action in ('dismiss', 'hide', 'delete')),
+  flag_ids integer[] not null,
+  note text,
+  created_on timestamptz not null default now()
+);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+drop trigger if exists answers_unaccept_deleted on answers;
+drop function if exists answers_unaccept_deleted();
@@ -0,0 +1,18 @@
+-- Add up migration script here
+-- Soft deleting, hiding or holding an answer takes back its acceptance,
+-- like `on delete set null` does when it is purged
+create or replace function answers_unaccept_deleted() returns trigger as $$
+begin
+  update questions set accepted_answer_id = null where accepted_answer_id = new.id;
+  return null;
+end
+$$ language plpgsql;
+
+create trigger answers_unaccept_deleted
+  after update of deleted_at on answers
+  for each row when (old.deleted_at is null and new.deleted_at is not null)
+  execute function answers_unaccept_deleted();
+
+update questions set accepted_answer_id = null
+from answers
+where answers.id = questions.accepted_answer_id and answers.deleted_at is not null;
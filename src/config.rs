@@ -0,0 +1,341 @@
+use std::net::{IpAddr, SocketAddr};
+
+use clap::Parser;
+use config::{ConfigError, Environment, File};
+use serde::Deserialize;
+
+/// Token secrets which were checked in at some point
+/// or are shipped in `setup.example.toml`, anyone can sign tokens with them
+const PLACEHOLDER_SECRETS: &[&str] = &[
+    "development-only-secret-change-me-please",
+    "replace-with-at-least-32-random-characters",
+];
+
+/// Command line flags. Every flag is optional and, when given,
+/// overrides the value coming from the config file or the environment.
+#[derive(Parser, Debug)]
+#[command(version, about = "Q&A web service")]
+struct Args {
+    /// Path to the configuration file (defaults to `setup.toml` if present)
+    #[arg(short, long)]
+    config: Option<String>,
+    /// Log level used for the `rwd` target, e.g. `info` or `debug`
+    #[arg(long)]
+    log_level: Option<String>,
+    /// Address the web server binds to
+    #[arg(long)]
+    host: Option<String>,
+    /// Port the web server listens on
+    #[arg(short, long)]
+    port: Option<u16>,
+    /// Storage backend, `postgres` or `memory`
+    #[arg(long)]
+    backend: Option<String>,
+    /// Postgres connection URL
+    #[arg(long)]
+    database_url: Option<String>,
+}
+
+/// Runtime configuration of the server.
+///
+/// Values are merged from (lowest to highest priority):
+/// built-in defaults, the config file, `RWD_*` environment variables
+/// (nested keys separated by `__`, e.g. `RWD_DATABASE__URL`)
+/// and command line flags.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub log_level: String,
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub profanity: ProfanityConfig,
+    pub auth: AuthConfig,
+    pub pagination: PaginationConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerConfig {
+    pub host: IpAddr,
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatabaseConfig {
+    pub backend: Backend,
+    /// Postgres connection URL, required by the `postgres` backend
+    pub url: Option<String>,
+    pub max_connections: u32,
+    /// JSON file the `memory` backend is seeded with
+    pub seed_file: Option<String>,
+}
+
+/// Storage backend the questions and answers are kept in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Postgres,
+    Memory,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthConfig {
+    /// Secret the bearer tokens are signed with, never committed to the
+    /// config file but passed in with `RWD_AUTH__TOKEN_SECRET`
+    #[serde(default)]
+    pub token_secret: String,
+    /// How long an issued token stays valid
+    pub token_ttl_minutes: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PaginationConfig {
+    /// Page size of listings requested without a `limit`
+    pub default_limit: u32,
+    /// Largest `limit` a client may ask for
+    pub max_limit: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProfanityConfig {
+    /// Content filters applied in order, each one on the output of the previous
+    pub filters: Vec<FilterKind>,
+    /// Character replacing every letter of a censored word
+    pub censor_character: char,
+    /// What happens to posts with at least `threshold` offending words
+    pub policy: Policy,
+    /// Offending words in the title and content of a post together from which
+    /// the policy applies, posts with fewer are censored
+    pub threshold: usize,
+    pub apilayer: ApiLayerConfig,
+    pub local: LocalFilterConfig,
+    pub cache: FilterCacheConfig,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterKind {
+    /// The APILayer bad words API
+    Apilayer,
+    /// The built-in word list and regex censor
+    Local,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Policy {
+    /// Store the post with the offending words censored
+    Censor,
+    /// Refuse the post, listing the offending words
+    Reject,
+    /// Store the censored post hidden until a moderator reviews it,
+    /// comments cannot be hidden and are refused like with `Reject`
+    Hold,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiLayerConfig {
+    /// Endpoint of the APILayer bad words API
+    pub url: String,
+    #[serde(default)]
+    pub api_key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FilterCacheConfig {
+    /// Checked texts whose results are kept, `0` disables the cache
+    pub capacity: usize,
+    /// How long a result is reused
+    pub ttl_seconds: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocalFilterConfig {
+    /// Words to censor
+    #[serde(default)]
+    pub words: Vec<String>,
+    /// File with additional words to censor, one per line
+    pub dictionary: Option<String>,
+    /// Regular expressions to censor
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Also match words disguised with digits and symbols, e.g. "5h1t"
+    pub leetspeak: bool,
+}
+
+impl Config {
+    /// Reads the configuration from all sources and validates it
+    pub fn new() -> Result<Config, ConfigError> {
+        let args = Args::parse();
+
+        // An explicitly passed config file has to exist,
+        // the default one is optional
+        let file = match args.config {
+            Some(ref path) => File::with_name(path).required(true),
+            None => File::with_name("setup").required(false),
+        };
+
+        let config = config::Config::builder()
+            .set_default("log_level", "info")?
+            .set_default("server.host", "0.0.0.0")?
+            .set_default("server.port", 3030)?
+            .set_default("database.backend", "postgres")?
+            .set_default("database.max_connections", 5)?
+            .set_default("auth.token_ttl_minutes", 24 * 60)?
+            .set_default("pagination.default_limit", 20)?
+            .set_default("pagination.max_limit", 100)?
+            .set_default("profanity.filters", vec!["apilayer"])?
+            .set_default("profanity.censor_character", "*")?
+            .set_default("profanity.policy", "censor")?
+            .set_default("profanity.threshold", 1)?
+            .set_default("profanity.apilayer.url", "https://api.apilayer.com/bad_words")?
+            .set_default("profanity.local.leetspeak", true)?
+            .set_default("profanity.cache.capacity", 1000)?
+            .set_default("profanity.cache.ttl_seconds", 60 * 60)?
+            .add_source(file)
+            .add_source(
+                Environment::with_prefix("RWD")
+                    .prefix_separator("_")
+                    .separator("__")
+                    .list_separator(",")
+                    .with_list_parse_key("profanity.filters")
+                    .with_list_parse_key("profanity.local.words")
+                    .with_list_parse_key("profanity.local.patterns")
+                    .try_parsing(true),
+            )
+            .set_override_option("log_level", args.log_level)?
+            .set_override_option("server.host", args.host)?
+            .set_override_option("server.port", args.port)?
+            .set_override_option("database.backend", args.backend)?
+            .set_override_option("database.url", args.database_url)?
+            .build()?
+            .try_deserialize::<Config>()?;
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Checks the values which can be deserialized but make no sense
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.log_level.trim().is_empty() {
+            return Err(invalid("log_level", "must not be empty"));
+        }
+
+        if self.server.port == 0 {
+            return Err(invalid("server.port", "must be between 1 and 65535"));
+        }
+
+        if self.database.backend == Backend::Postgres {
+            match self.database.url.as_deref().filter(|url| !url.is_empty()) {
+                Some(url)
+                    if url.starts_with("postgres://") || url.starts_with("postgresql://") => {}
+                Some(_) => {
+                    return Err(invalid(
+                        "database.url",
+                        "must be a postgres:// or postgresql:// URL",
+                    ));
+                }
+                None => {
+                    return Err(invalid(
+                        "database.url",
+                        "is required by the postgres backend, set it with `RWD_DATABASE__URL`",
+                    ));
+                }
+            }
+        }
+
+        // Only the memory backend reads the seed file
+        if self.database.backend == Backend::Memory
+            && let Some(ref path) = self.database.seed_file
+            && !std::path::Path::new(path).is_file()
+        {
+            return Err(invalid("database.seed_file", "file does not exist"));
+        }
+
+        if self.database.max_connections == 0 {
+            return Err(invalid("database.max_connections", "must be at least 1"));
+        }
+
+        if self.auth.token_secret.is_empty() {
+            return Err(invalid(
+                "auth.token_secret",
+                "is required, set it with `RWD_AUTH__TOKEN_SECRET`",
+            ));
+        }
+
+        if PLACEHOLDER_SECRETS.contains(&self.auth.token_secret.as_str()) {
+            return Err(invalid(
+                "auth.token_secret",
+                "is a published placeholder, generate a random secret",
+            ));
+        }
+
+        if self.auth.token_secret.len() < 32 {
+            return Err(invalid("auth.token_secret", "must be at least 32 characters long"));
+        }
+
+        if self.auth.token_ttl_minutes <= 0 {
+            return Err(invalid("auth.token_ttl_minutes", "must be positive"));
+        }
+
+        if self.pagination.max_limit == 0 {
+            return Err(invalid("pagination.max_limit", "must be at least 1"));
+        }
+
+        if self.pagination.default_limit == 0
+            || self.pagination.default_limit > self.pagination.max_limit
+        {
+            return Err(invalid(
+                "pagination.default_limit",
+                "must be between 1 and `pagination.max_limit`",
+            ));
+        }
+
+        if self.profanity.filters.is_empty() {
+            return Err(invalid("profanity.filters", "must select at least one filter"));
+        }
+
+        if self.profanity.threshold == 0 {
+            return Err(invalid("profanity.threshold", "must be at least 1"));
+        }
+
+        if self.profanity.filters.contains(&FilterKind::Apilayer) {
+            if let Err(e) = reqwest::Url::parse(&self.profanity.apilayer.url) {
+                return Err(invalid("profanity.apilayer.url", &e.to_string()));
+            }
+
+            if self.profanity.apilayer.api_key.trim().is_empty() {
+                return Err(invalid(
+                    "profanity.apilayer.api_key",
+                    "is required by the apilayer filter, set it with `RWD_PROFANITY__APILAYER__API_KEY`",
+                ));
+            }
+        }
+
+        if self.profanity.cache.capacity > 0 && self.profanity.cache.ttl_seconds == 0 {
+            return Err(invalid("profanity.cache.ttl_seconds", "must be positive"));
+        }
+
+        let local = &self.profanity.local;
+        if self.profanity.filters.contains(&FilterKind::Local)
+            && local.words.is_empty()
+            && local.patterns.is_empty()
+            && local.dictionary.is_none()
+        {
+            return Err(invalid(
+                "profanity.local",
+                "needs `words`, `patterns` or a `dictionary`",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Address the web server binds to
+    pub fn address(&self) -> SocketAddr {
+        SocketAddr::new(self.server.host, self.server.port)
+    }
+}
+
+fn invalid(key: &str, reason: &str) -> ConfigError {
+    ConfigError::Message(format!("invalid value for `{}`: {}", key, reason))
+}
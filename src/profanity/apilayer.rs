@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+use handle_errors::{Error, APILayerError};
+
+use async_trait::async_trait;
+
+use super::{BadWord as FoundWord, Checked, ContentFilter};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct APIResponse(String);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BadWord {
+    original: String,
+    word: String,
+    deviations: i64,
+    info: i64,
+    start: usize,
+    end: usize,
+    #[serde(rename = "replacedLen")]
+    replaced_len: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BadWordsResponse {
+    content: String,
+    bad_words_total: i64,
+    bad_words_list: Vec<BadWord>,
+    censored_content: String,
+}
+
+/// Content filter backed by the APILayer bad words API
+#[derive(Debug, Clone)]
+pub struct ApiLayer {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+    censor_character: char,
+}
+
+impl ApiLayer {
+    pub fn new(url: &str, api_key: &str, censor_character: char) -> Self {
+        ApiLayer {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+            api_key: api_key.to_string(),
+            censor_character,
+        }
+    }
+}
+
+#[async_trait]
+impl ContentFilter for ApiLayer {
+    async fn check(&self, content: String) -> Result<Checked, Error> {
+        let res = self
+            .client
+            .post(&self.url)
+            .query(&[("censor_character", self.censor_character.to_string())])
+            .header("apikey", &self.api_key)
+            .body(content)
+            .send()
+            .await
+            .map_err(Error::ExternalAPIError)?;
+
+        if !res.status().is_success() {
+            let status = res.status().as_u16();
+            let body = res.text().await.map_err(Error::ExternalAPIError)?;
+            // Errors usually come as a JSON string, anything else (e.g. a
+            // gateway's HTML page) is passed on as it is
+            let message = match serde_json::from_str::<APIResponse>(&body) {
+                Ok(message) => message.0,
+                Err(_) => body,
+            };
+
+            let err = APILayerError { status, message };
+
+            if status < 500 {
+                return Err(Error::ClientError(err));
+            } else {
+                return Err(Error::ServerError(err));
+            }
+        }
+
+        match res.json::<BadWordsResponse>().await {
+            Ok(res) => Ok(Checked {
+                censored: res.censored_content,
+                bad_words: res
+                    .bad_words_list
+                    .into_iter()
+                    .map(|w| FoundWord {
+                        word: w.original,
+                        start: w.start,
+                        end: w.end,
+                    })
+                    .collect(),
+            }),
+            Err(e) => Err(Error::ExternalAPIError(e)),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use handle_errors::Error;
+
+use async_trait::async_trait;
+use lru::LruCache;
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::{Checked, ContentFilter};
+
+/// Remembers what the wrapped filter found for recently checked texts,
+/// so text submitted again, like the unchanged title of an edited
+/// question, does not go to the filter a second time
+#[derive(Debug)]
+pub struct Cached {
+    filter: Arc<dyn ContentFilter>,
+    ttl: Duration,
+    /// Results by SHA-256 of the checked text, with the time they were stored
+    entries: Mutex<LruCache<[u8; 32], (Instant, Checked)>>,
+}
+
+impl Cached {
+    pub fn new(filter: Arc<dyn ContentFilter>, capacity: NonZeroUsize, ttl: Duration) -> Self {
+        Cached {
+            filter,
+            ttl,
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+}
+
+#[async_trait]
+impl ContentFilter for Cached {
+    async fn check(&self, content: String) -> Result<Checked, Error> {
+        let key: [u8; 32] = Sha256::digest(content.as_bytes()).into();
+
+        // The lock is not held across the check, concurrent misses
+        // on the same text both ask the filter
+        let cached = match self.entries.lock().get(&key) {
+            Some((stored, checked)) if stored.elapsed() < self.ttl => Some(checked.clone()),
+            _ => None,
+        };
+        if let Some(checked) = cached {
+            return Ok(checked);
+        }
+
+        let checked = self.filter.check(content).await?;
+        self.entries
+            .lock()
+            .put(key, (Instant::now(), checked.clone()));
+
+        Ok(checked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts the texts it is asked to check and censors none of them
+    #[derive(Debug, Default)]
+    struct Counting {
+        checks: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ContentFilter for Counting {
+        async fn check(&self, content: String) -> Result<Checked, Error> {
+            self.checks.fetch_add(1, Ordering::SeqCst);
+            Ok(Checked {
+                censored: content,
+                bad_words: Vec::new(),
+            })
+        }
+    }
+
+    fn cached(capacity: usize, ttl: Duration) -> (Arc<Counting>, Cached) {
+        let counting = Arc::new(Counting::default());
+        let capacity = NonZeroUsize::new(capacity).unwrap();
+        (counting.clone(), Cached::new(counting, capacity, ttl))
+    }
+
+    /// Checks the texts one after another and returns how many reached the filter
+    async fn check(counting: &Counting, cached: &Cached, texts: &[&str]) -> usize {
+        let before = counting.checks.load(Ordering::SeqCst);
+        for text in texts {
+            let checked = cached.check(text.to_string()).await.unwrap();
+            assert_eq!(checked.censored, *text);
+        }
+        counting.checks.load(Ordering::SeqCst) - before
+    }
+
+    #[tokio::test]
+    async fn hits_skip_the_filter() {
+        let (counting, cached) = cached(10, Duration::from_secs(60));
+
+        assert_eq!(check(&counting, &cached, &["a title", "some content"]).await, 2);
+        assert_eq!(check(&counting, &cached, &["a title", "some content"]).await, 0);
+        assert_eq!(check(&counting, &cached, &["another title"]).await, 1);
+    }
+
+    #[tokio::test]
+    async fn entries_expire_after_the_ttl() {
+        let (counting, cached) = cached(10, Duration::from_millis(50));
+
+        assert_eq!(check(&counting, &cached, &["a title", "a title"]).await, 1);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(check(&counting, &cached, &["a title", "a title"]).await, 1);
+    }
+
+    #[tokio::test]
+    async fn the_least_recently_used_entry_is_evicted() {
+        let (counting, cached) = cached(2, Duration::from_secs(60));
+
+        // "a" was used after "b", so "c" pushes out "b"
+        assert_eq!(check(&counting, &cached, &["a", "b", "a", "c"]).await, 3);
+        assert_eq!(check(&counting, &cached, &["a", "c"]).await, 0);
+        assert_eq!(check(&counting, &cached, &["b"]).await, 1);
+    }
+}
@@ -0,0 +1,280 @@
+use handle_errors::Error;
+
+use async_trait::async_trait;
+use config::ConfigError;
+use regex::Regex;
+
+use std::ops::Range;
+
+use super::{BadWord, Checked, ContentFilter};
+use crate::config::LocalFilterConfig;
+
+/// Endings a dictionary word may carry and still be censored,
+/// so "damn" also matches "damns", "damned" and "damning"
+const SUFFIXES: &str = "(?:s|es|ed|er|ers|ing|in)?";
+
+/// Content filter working offline on a word list and regular expressions
+#[derive(Debug, Clone)]
+pub struct LocalCensor {
+    /// The dictionary words, also matched on the text with leetspeak undone
+    words: Option<Regex>,
+    /// The raw regular expressions, only matched on the text as written
+    patterns: Option<Regex>,
+    censor_character: char,
+    leetspeak: bool,
+}
+
+impl LocalCensor {
+    /// Combines the dictionary words and the raw regular expressions
+    /// into one case insensitive pattern each
+    pub fn new(
+        words: &[String],
+        patterns: &[String],
+        censor_character: char,
+        leetspeak: bool,
+    ) -> Result<Self, regex::Error> {
+        let words = match words.is_empty() {
+            true => None,
+            false => {
+                let words: Vec<String> = words.iter().map(|w| regex::escape(w)).collect();
+                Some(Regex::new(&format!(r"(?i)\b(?:{}){}\b", words.join("|"), SUFFIXES))?)
+            }
+        };
+
+        for pattern in patterns {
+            // Compile each one on its own first to report the broken one
+            Regex::new(pattern)?;
+        }
+
+        let patterns = match patterns.is_empty() {
+            true => None,
+            false => {
+                let patterns: Vec<String> = patterns.iter().map(|p| format!("(?:{})", p)).collect();
+                Some(Regex::new(&format!("(?i){}", patterns.join("|")))?)
+            }
+        };
+
+        Ok(LocalCensor {
+            words,
+            patterns,
+            censor_character,
+            leetspeak,
+        })
+    }
+
+    pub fn from_config(
+        config: &LocalFilterConfig,
+        censor_character: char,
+    ) -> Result<Self, ConfigError> {
+        let mut words = config.words.clone();
+
+        if let Some(ref path) = config.dictionary {
+            let dictionary = std::fs::read_to_string(path).map_err(|e| {
+                ConfigError::Message(format!("cannot read dictionary {}: {}", path, e))
+            })?;
+
+            // One word per line, `#` starts a comment
+            words.extend(
+                dictionary
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(str::to_string),
+            );
+        }
+
+        LocalCensor::new(&words, &config.patterns, censor_character, config.leetspeak)
+            .map_err(|e| ConfigError::Message(format!("invalid profanity pattern: {}", e)))
+    }
+
+    /// Replaces every character of a matched word with the censor character
+    pub fn censor(&self, content: &str) -> Checked {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+
+        if let Some(ref words) = self.words {
+            ranges.extend(words.find_iter(content).map(|m| m.range()));
+
+            // Only ASCII characters get replaced by ASCII characters,
+            // so byte offsets in the normalized text match the original one
+            if self.leetspeak {
+                let normalized = normalize_leetspeak(content);
+                ranges.extend(words.find_iter(&normalized).map(|m| m.range()));
+            }
+        }
+
+        if let Some(ref patterns) = self.patterns {
+            ranges.extend(patterns.find_iter(content).map(|m| m.range()));
+        }
+
+        // Overlapping matches of the two texts and the patterns become one word
+        ranges.sort_by_key(|range| range.start);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start < last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+
+        let mut censored = String::with_capacity(content.len());
+        let mut bad_words = Vec::new();
+        let mut last = 0;
+        // Characters before `last`, positions are reported in characters
+        let mut offset = 0;
+
+        for range in merged {
+            let before = &content[last..range.start];
+            let word = &content[range.clone()];
+            let start = offset + before.chars().count();
+            let length = word.chars().count();
+
+            censored.push_str(before);
+            censored.extend(std::iter::repeat_n(self.censor_character, length));
+            bad_words.push(BadWord {
+                word: word.to_string(),
+                start,
+                end: start + length,
+            });
+
+            last = range.end;
+            offset = start + length;
+        }
+        censored.push_str(&content[last..]);
+
+        Checked {
+            censored,
+            bad_words,
+        }
+    }
+}
+
+#[async_trait]
+impl ContentFilter for LocalCensor {
+    async fn check(&self, content: String) -> Result<Checked, Error> {
+        Ok(self.censor(&content))
+    }
+}
+
+/// Maps digits and symbols commonly used to disguise words
+/// back to the letters they stand for, e.g. "5h1t" -> "shit".
+/// Symbols are only mapped in front of a letter or digit, so punctuation
+/// ending a word, like in "shit!", keeps the word boundary where it is
+fn normalize_leetspeak(content: &str) -> String {
+    let mut chars = content.chars().peekable();
+    let mut normalized = String::with_capacity(content.len());
+
+    while let Some(c) = chars.next() {
+        let in_word = chars.peek().is_some_and(|next| next.is_alphanumeric());
+        normalized.push(match c {
+            '0' => 'o',
+            '1' => 'i',
+            '3' => 'e',
+            '4' => 'a',
+            '5' => 's',
+            '7' => 't',
+            '!' | '|' if in_word => 'i',
+            '@' if in_word => 'a',
+            '$' if in_word => 's',
+            '+' if in_word => 't',
+            c => c,
+        });
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn censor(words: &[&str], patterns: &[&str], leetspeak: bool) -> LocalCensor {
+        let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        LocalCensor::new(&words, &patterns, '*', leetspeak).unwrap()
+    }
+
+    fn words(checked: &Checked) -> Vec<(&str, usize, usize)> {
+        checked
+            .bad_words
+            .iter()
+            .map(|w| (w.word.as_str(), w.start, w.end))
+            .collect()
+    }
+
+    #[test]
+    fn censors_words_and_their_endings() {
+        let checked = censor(&["damn"], &[], false).censor("Damning is no damn, damned!");
+
+        assert_eq!(checked.censored, "******* is no ****, ******!");
+        assert_eq!(
+            words(&checked),
+            vec![("Damning", 0, 7), ("damn", 14, 18), ("damned", 20, 26)]
+        );
+    }
+
+    #[test]
+    fn leaves_words_containing_a_bad_word_alone() {
+        let checked = censor(&["ass"], &[], false).censor("a classic assessment");
+
+        assert_eq!(checked.censored, "a classic assessment");
+        assert!(checked.bad_words.is_empty());
+    }
+
+    #[test]
+    fn reports_character_offsets() {
+        let checked = censor(&["shit"], &[], false).censor("Grüße, shit");
+
+        assert_eq!(checked.censored, "Grüße, ****");
+        assert_eq!(words(&checked), vec![("shit", 7, 11)]);
+    }
+
+    #[test]
+    fn sees_through_leetspeak_only_when_enabled() {
+        assert_eq!(censor(&["shit"], &[], true).censor("5h1t").censored, "****");
+        assert_eq!(censor(&["shit"], &[], false).censor("5h1t").censored, "5h1t");
+    }
+
+    #[test]
+    fn applies_patterns() {
+        let checked = censor(&[], &[r"https?://spam\.example\S*"], false)
+            .censor("see http://spam.example/buy now");
+
+        assert_eq!(checked.censored, "see *********************** now");
+        assert_eq!(words(&checked), vec![("http://spam.example/buy", 4, 27)]);
+    }
+
+    #[test]
+    fn without_words_nothing_is_censored() {
+        let checked = censor(&[], &[], true).censor("anything goes");
+
+        assert_eq!(checked.censored, "anything goes");
+        assert!(checked.bad_words.is_empty());
+    }
+
+    #[test]
+    fn rejects_broken_patterns() {
+        assert!(LocalCensor::new(&[], &["(unclosed".to_string()], '*', false).is_err());
+    }
+
+    #[test]
+    fn trailing_punctuation_does_not_hide_a_word() {
+        let censor = censor(&["shit", "damn"], &[], true);
+
+        assert_eq!(censor.censor("shit!").censored, "****!");
+        assert_eq!(censor.censor("damn$").censored, "****$");
+        assert_eq!(censor.censor("sh1t! 5h!t").censored, "****! ****");
+        assert_eq!(words(&censor.censor("oh !shit")), vec![("shit", 4, 8)]);
+    }
+
+    #[test]
+    fn patterns_match_the_text_as_written() {
+        let censor = censor(&["shit"], &[r"b1tc01n\$", r"\bv1agra\b"], true);
+
+        let checked = censor.censor("buy v1agra for b1tc01n$ or sh1t");
+        assert_eq!(checked.censored, "buy ****** for ******** or ****");
+        assert_eq!(
+            words(&checked),
+            vec![("v1agra", 4, 10), ("b1tc01n$", 15, 23), ("sh1t", 27, 31)]
+        );
+    }
+}
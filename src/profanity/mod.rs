@@ -0,0 +1,301 @@
+mod apilayer;
+mod cache;
+mod local;
+
+pub use apilayer::ApiLayer;
+pub use cache::Cached;
+pub use local::LocalCensor;
+
+use crate::config::{FilterKind, Policy, ProfanityConfig};
+use crate::types::moderation::{FlagReason, NewFlag};
+
+use handle_errors::{Error, ProfaneWord};
+
+use async_trait::async_trait;
+use config::ConfigError;
+
+use std::fmt::Debug;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// An offending word a filter found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BadWord {
+    /// The word as written in the text
+    pub word: String,
+    /// Character offsets in the text, `end` excluded
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Outcome of checking a text
+#[derive(Debug, Clone)]
+pub struct Checked {
+    /// The text with every offending word censored, one censor
+    /// character per character, so offsets match the original
+    pub censored: String,
+    /// Offending words in the order they appear
+    pub bad_words: Vec<BadWord>,
+}
+
+/// Checks user provided text before it gets stored
+#[async_trait]
+pub trait ContentFilter: Send + Sync + Debug {
+    async fn check(&self, content: String) -> Result<Checked, Error>;
+}
+
+/// Runs several filters one after another,
+/// each one getting the output of the previous one
+#[derive(Debug, Clone)]
+pub struct Chain {
+    filters: Vec<Arc<dyn ContentFilter>>,
+}
+
+impl Chain {
+    pub fn new(filters: Vec<Arc<dyn ContentFilter>>) -> Self {
+        Chain { filters }
+    }
+}
+
+#[async_trait]
+impl ContentFilter for Chain {
+    async fn check(&self, content: String) -> Result<Checked, Error> {
+        let mut checked = Checked {
+            censored: content,
+            bad_words: Vec::new(),
+        };
+
+        // Words censored by one filter are hidden from the next ones,
+        // so no word is reported twice
+        for filter in &self.filters {
+            let next = filter.check(checked.censored).await?;
+            checked.censored = next.censored;
+            checked.bad_words.extend(next.bad_words);
+        }
+        checked.bad_words.sort_by_key(|w| w.start);
+
+        Ok(checked)
+    }
+}
+
+/// A field of a post after the content filters ran on it
+#[derive(Debug, Clone)]
+pub struct Screened {
+    pub field: &'static str,
+    /// The field with every offending word censored
+    pub content: String,
+    pub bad_words: Vec<BadWord>,
+}
+
+/// What happens to a post the policy lets through
+#[derive(Debug, Clone)]
+pub enum Verdict {
+    Publish,
+    /// Store the post hidden and raise this flag for the moderators
+    Hold(NewFlag),
+}
+
+impl Verdict {
+    /// The flag a held post is stored hidden with
+    pub fn into_hold(self) -> Option<NewFlag> {
+        match self {
+            Verdict::Publish => None,
+            Verdict::Hold(flag) => Some(flag),
+        }
+    }
+}
+
+/// Runs the content filters and applies the configured policy to what they find
+#[derive(Debug, Clone)]
+pub struct Screen {
+    filter: Arc<dyn ContentFilter>,
+    policy: Policy,
+    threshold: usize,
+}
+
+impl Screen {
+    pub fn new(filter: Arc<dyn ContentFilter>, policy: Policy, threshold: usize) -> Self {
+        Screen {
+            filter,
+            policy,
+            threshold,
+        }
+    }
+
+    pub async fn check(&self, field: &'static str, content: String) -> Result<Screened, Error> {
+        let checked = self.filter.check(content).await?;
+
+        Ok(Screened {
+            field,
+            content: checked.censored,
+            bad_words: checked.bad_words,
+        })
+    }
+
+    /// The error refusing a post, listing the offending words of every field
+    pub fn rejection(fields: &[&Screened]) -> Error {
+        Error::ProfaneContent(
+            fields
+                .iter()
+                .flat_map(|f| {
+                    f.bad_words.iter().map(|w| ProfaneWord {
+                        field: f.field.to_string(),
+                        word: w.word.clone(),
+                        start: w.start,
+                        end: w.end,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// Decides on a post from all of its screened fields,
+    /// fails with `ProfaneContent` if the policy rejects it
+    pub fn verdict(&self, fields: &[&Screened]) -> Result<Verdict, Error> {
+        let total: usize = fields.iter().map(|f| f.bad_words.len()).sum();
+
+        if total < self.threshold {
+            return Ok(Verdict::Publish);
+        }
+
+        match self.policy {
+            Policy::Censor => Ok(Verdict::Publish),
+            Policy::Reject => Err(Screen::rejection(fields)),
+            Policy::Hold => {
+                let mut words: Vec<String> = fields
+                    .iter()
+                    .flat_map(|f| f.bad_words.iter().map(|w| w.word.to_lowercase()))
+                    .collect();
+                words.sort();
+                words.dedup();
+
+                Ok(Verdict::Hold(NewFlag {
+                    reason: FlagReason::Offensive,
+                    details: Some(format!(
+                        "Held for {} offending word(s): {}",
+                        total,
+                        words.join(", ")
+                    )),
+                }))
+            }
+        }
+    }
+}
+
+/// Builds the filter chain and the policy selected in the configuration
+pub fn from_config(config: &ProfanityConfig) -> Result<Screen, ConfigError> {
+    let mut filters: Vec<Arc<dyn ContentFilter>> = Vec::new();
+
+    for kind in &config.filters {
+        match kind {
+            FilterKind::Apilayer => filters.push(Arc::new(ApiLayer::new(
+                &config.apilayer.url,
+                &config.apilayer.api_key,
+                config.censor_character,
+            ))),
+            FilterKind::Local => filters.push(Arc::new(LocalCensor::from_config(
+                &config.local,
+                config.censor_character,
+            )?)),
+        }
+    }
+
+    let mut filter: Arc<dyn ContentFilter> = match filters.len() {
+        1 => filters.remove(0),
+        _ => Arc::new(Chain::new(filters)),
+    };
+
+    if let Some(capacity) = NonZeroUsize::new(config.cache.capacity) {
+        filter = Arc::new(Cached::new(
+            filter,
+            capacity,
+            Duration::from_secs(config.cache.ttl_seconds),
+        ));
+    }
+
+    Ok(Screen::new(filter, config.policy, config.threshold))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen(policy: Policy, threshold: usize) -> Screen {
+        let censor = LocalCensor::new(&["shit".to_string(), "damn".to_string()], &[], '*', false);
+        Screen::new(Arc::new(censor.unwrap()), policy, threshold)
+    }
+
+    async fn post(screen: &Screen, title: &str, content: &str) -> (Screened, Screened) {
+        tokio::try_join!(
+            screen.check("title", title.to_string()),
+            screen.check("content", content.to_string()),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn clean_posts_are_published() {
+        for policy in [Policy::Censor, Policy::Reject, Policy::Hold] {
+            let screen = screen(policy, 1);
+            let (title, content) = post(&screen, "hello", "nothing to see").await;
+
+            assert!(matches!(screen.verdict(&[&title, &content]), Ok(Verdict::Publish)));
+        }
+    }
+
+    #[tokio::test]
+    async fn censor_publishes_censored_content() {
+        let screen = screen(Policy::Censor, 1);
+        let (title, content) = post(&screen, "damn", "oh shit").await;
+
+        assert_eq!(title.content, "****");
+        assert_eq!(content.content, "oh ****");
+        assert!(matches!(screen.verdict(&[&title, &content]), Ok(Verdict::Publish)));
+    }
+
+    #[tokio::test]
+    async fn reject_lists_the_words_of_every_field() {
+        let screen = screen(Policy::Reject, 1);
+        let (title, content) = post(&screen, "Damn", "oh shit").await;
+
+        match screen.verdict(&[&title, &content]) {
+            Err(Error::ProfaneContent(words)) => {
+                let words: Vec<(&str, &str, usize, usize)> = words
+                    .iter()
+                    .map(|w| (w.field.as_str(), w.word.as_str(), w.start, w.end))
+                    .collect();
+                assert_eq!(words, vec![("title", "Damn", 0, 4), ("content", "shit", 3, 7)]);
+            }
+            other => panic!("expected profane content, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn hold_raises_an_offensive_flag() {
+        let screen = screen(Policy::Hold, 1);
+        let (title, content) = post(&screen, "Shit", "shit, damn").await;
+
+        match screen.verdict(&[&title, &content]) {
+            Ok(Verdict::Hold(flag)) => {
+                assert_eq!(flag.reason, FlagReason::Offensive);
+                assert_eq!(
+                    flag.details.as_deref(),
+                    Some("Held for 3 offending word(s): damn, shit")
+                );
+            }
+            other => panic!("expected a hold, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn posts_below_the_threshold_are_published() {
+        for policy in [Policy::Reject, Policy::Hold] {
+            let screen = screen(policy, 3);
+            let (title, content) = post(&screen, "damn", "shit").await;
+
+            assert!(matches!(screen.verdict(&[&title, &content]), Ok(Verdict::Publish)));
+            assert_eq!(content.content, "****");
+        }
+    }
+}
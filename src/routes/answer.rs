@@ -1,24 +1,329 @@
+use crate::config::PaginationConfig;
+use crate::routes::pagination::paginated_reply;
 use crate::store::Store;
-use crate::types::answer::NewAnswer;
-use crate::profanity::check_profanity;
+use crate::types::account::Session;
+use crate::types::answer::{AnswerId, NewAnswer, UpdateAnswer};
+use crate::types::moderation::FlagTarget;
+use crate::types::question::check_post_size;
+use crate::types::revision::answer_diffs;
+use crate::types::vote::NewVote;
+use crate::types::pagination::{PAGINATION_PARAMS, extract_pagination, reject_unknown_params};
+use crate::profanity::Screen;
+
+use handle_errors::Error;
+
+use std::collections::HashMap;
 
 use warp::{Rejection, Reply, http::StatusCode};
 
-pub async fn add_answer(answer: NewAnswer, store: Store) -> Result<impl Reply, Rejection> {
-    let content = match check_profanity(answer.content).await {
-        Ok(res) => res,
-        Err(e) => return Err(warp::reject::custom(e)),
-    };
+use tracing::{Level, event};
+
+pub async fn get_answers(
+    question_id: i32,
+    params: HashMap<String, String>,
+    config: PaginationConfig,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "querying answers of question {}", question_id);
+
+    reject_unknown_params(&params, &PAGINATION_PARAMS)?;
+    let pagination = extract_pagination(&params, &config)?;
+
+    match store.get_answers(question_id, &pagination).await {
+        Ok(page) => Ok(paginated_reply(
+            &format!("/questions/{}/answers", question_id),
+            &params,
+            &pagination,
+            page,
+            true,
+        )),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+pub async fn get_answer(id: i32, store: Store) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "querying answer with id: {}", id);
+
+    match store.get_answer(id).await {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+pub async fn add_answer(
+    session: Session,
+    answer: NewAnswer,
+    store: Store,
+    profanity: Screen,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} adding answer", session.account_id);
+
+    check_post_size(None, &answer.content)?;
+    let content = profanity.check("content", answer.content).await?;
+    let verdict = profanity.verdict(&[&content])?;
 
     let answer = NewAnswer {
-	content,
+	content: content.content,
 	question_id: answer.question_id,
     };
 
-    println!("{:?}", &answer);
+    event!(target: "rwd", Level::DEBUG, "{:?}", &answer);
+
+    let hold = verdict.into_hold();
+    let held = hold.is_some();
+    store.add_answer(answer, &session.account_id, hold).await?;
+
+    if held {
+        Ok(warp::reply::with_status(
+            "Answer held for review",
+            StatusCode::ACCEPTED,
+        ))
+    } else {
+        Ok(warp::reply::with_status("Answer added", StatusCode::OK))
+    }
+}
+
+pub async fn update_answer(
+    id: i32,
+    session: Session,
+    store: Store,
+    profanity: Screen,
+    answer: UpdateAnswer,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} updating answer {}", session.account_id, id);
+
+    if !session.is_moderator() && !store.is_answer_owner(id, &session.account_id).await? {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    check_post_size(None, &answer.content)?;
+    let content = profanity.check("content", answer.content).await?;
+    let hold = profanity.verdict(&[&content])?.into_hold();
+    let status = if hold.is_some() {
+        StatusCode::ACCEPTED
+    } else {
+        StatusCode::OK
+    };
+
+    let answer = store
+        .update_answer(
+            UpdateAnswer {
+                content: content.content,
+            },
+            id,
+            &session.account_id,
+            hold,
+        )
+        .await?;
+
+    Ok(warp::reply::with_status(warp::reply::json(&answer), status))
+}
+
+pub async fn delete_answer(
+    id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} deleting answer {}", session.account_id, id);
+
+    if !session.is_moderator() && !store.is_answer_owner(id, &session.account_id).await? {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    match store.delete_answer(id).await {
+        Ok(_) => Ok(warp::reply::with_status(
+            format!("Answer {} deleted", id),
+            StatusCode::OK,
+        )),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Brings back a deleted answer
+pub async fn restore_answer(
+    id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} restoring answer {}", session.account_id, id);
 
-    match store.add_answer(answer).await {
-        Ok(_) => Ok(warp::reply::with_status("Answer added", StatusCode::OK)),
+    // Posts hidden by a moderator stay hidden until a moderator says otherwise
+    if !session.is_moderator()
+        && (!store.is_answer_owner(id, &session.account_id).await?
+            || store.is_hidden(&FlagTarget::Answer(AnswerId(id))).await?)
+    {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    match store.restore_answer(id).await {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Votes an answer up or down, voting again changes the vote
+pub async fn vote_answer(
+    id: i32,
+    session: Session,
+    store: Store,
+    vote: NewVote,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} voting on answer {}", session.account_id, id);
+
+    match store.vote_answer(id, &session.account_id, vote.vote).await {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Lists the versions of an answer, oldest first, with the changes each one made
+/// # Example query
+/// `/answers/1/revisions?limit=10&offset=10`
+pub async fn get_answer_revisions(
+    id: i32,
+    params: HashMap<String, String>,
+    config: PaginationConfig,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "querying revisions of answer {}", id);
+
+    reject_unknown_params(&params, &["limit", "offset"])?;
+    let pagination = extract_pagination(&params, &config)?;
+
+    // The revision before the page is only fetched to diff the first one against
+    let before = pagination.offset.min(1);
+
+    match store
+        .get_answer_revisions(id, pagination.limit + before, pagination.offset - before)
+        .await
+    {
+        Ok(revisions) => {
+            let diffs: Vec<_> = answer_diffs(revisions)
+                .into_iter()
+                .skip(before as usize)
+                .collect();
+            Ok(warp::reply::json(&diffs))
+        }
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Restores an earlier version of an answer by recording it as a new revision
+pub async fn rollback_answer(
+    id: i32,
+    revision: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(
+        target: "rwd",
+        Level::INFO,
+        "account {} rolling answer {} back to revision {}",
+        session.account_id,
+        id,
+        revision
+    );
+
+    if !session.is_moderator() && !store.is_answer_owner(id, &session.account_id).await? {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    let revision = store.get_answer_revision(id, revision).await?;
+    let answer = UpdateAnswer {
+        content: revision.content,
+    };
+
+    match store
+        .update_answer(answer, id, &session.account_id, None)
+        .await
+    {
+        Ok(res) => Ok(warp::reply::json(&res)),
         Err(e) => Err(warp::reject::custom(e)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Policy;
+    use crate::routes::testing::{app, body, request};
+    use crate::types::account::Role;
+
+    use serde_json::json;
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    async fn answers_are_voted_on_once_per_account() {
+        let app = app(Policy::Censor);
+        let ann = app.sign_up("ann@example.com", Role::User).await;
+        let bob = app.sign_up("bob@example.com", Role::User).await;
+        app.ask(&ann, "A title", "Some content").await;
+        app.answer(&bob, 1, "An answer").await;
+
+        // Voting the same way twice counts once
+        for (token, vote, score) in [(&ann, "down", -1), (&ann, "down", -1), (&bob, "up", 0)] {
+            let voted = app
+                .send(request("POST", "/answers/1/vote", Some(token)).json(&json!({ "vote": vote })))
+                .await;
+            assert_eq!(voted.status(), StatusCode::OK);
+            assert_eq!(body(&voted)["score"], score);
+        }
+
+        let anonymous = app
+            .send(request("POST", "/answers/1/vote", None).json(&json!({ "vote": "up" })))
+            .await;
+        assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn deleted_or_hidden_answers_lose_their_acceptance() {
+        let app = app(Policy::Censor);
+        let ann = app.sign_up("ann@example.com", Role::User).await;
+        let bob = app.sign_up("bob@example.com", Role::User).await;
+        let moderator = app.sign_up("mod@example.com", Role::Moderator).await;
+        app.ask(&ann, "A title", "Some content").await;
+        app.answer(&bob, 1, "First answer").await;
+        app.answer(&bob, 1, "Second answer").await;
+
+        let accepted = || async {
+            let fetched = app.send(request("GET", "/questions/1", None)).await;
+            body(&fetched)["accepted_answer_id"].clone()
+        };
+
+        app.send(request("POST", "/questions/1/accept/1", Some(&ann))).await;
+        assert_eq!(accepted().await, 1);
+        let deleted = app.send(request("DELETE", "/answers/1", Some(&bob))).await;
+        assert_eq!(deleted.status(), StatusCode::OK);
+        assert!(accepted().await.is_null());
+        let restored = app
+            .send(request("POST", "/answers/1/restore", Some(&bob)))
+            .await;
+        assert_eq!(restored.status(), StatusCode::OK);
+        assert!(accepted().await.is_null());
+
+        app.send(request("POST", "/questions/1/accept/2", Some(&ann))).await;
+        assert_eq!(accepted().await, 2);
+        let hidden = app
+            .send(
+                request("POST", "/answers/2/moderate", Some(&moderator))
+                    .json(&json!({ "action": "hide" })),
+            )
+            .await;
+        assert_eq!(hidden.status(), StatusCode::OK);
+        assert!(accepted().await.is_null());
+        let dismissed = app
+            .send(
+                request("POST", "/answers/2/moderate", Some(&moderator))
+                    .json(&json!({ "action": "dismiss" })),
+            )
+            .await;
+        assert_eq!(dismissed.status(), StatusCode::OK);
+        assert!(accepted().await.is_null());
+
+        // Only answers which are there can be accepted
+        app.send(request("DELETE", "/answers/2", Some(&bob))).await;
+        let accept = app
+            .send(request("POST", "/questions/1/accept/2", Some(&ann)))
+            .await;
+        assert_eq!(accept.status(), StatusCode::NOT_FOUND);
+    }
+}
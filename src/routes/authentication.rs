@@ -0,0 +1,196 @@
+use crate::config::AuthConfig;
+use crate::store::Store;
+use crate::types::account::{Account, Role, Session};
+
+use handle_errors::{Error, FieldError};
+
+use chrono::{Duration, Utc};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use rand::Rng;
+use std::sync::LazyLock;
+use warp::{Filter, Rejection, Reply, http::StatusCode};
+
+use tracing::{Level, event};
+
+/// Passwords shorter than this are rejected at registration
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Checked instead of a stored hash when the address is unknown,
+/// so such logins take as long as ones with a wrong password
+static DUMMY_HASH: LazyLock<String> = LazyLock::new(|| {
+    let salt = rand::thread_rng().r#gen::<[u8; 32]>();
+    argon2::hash_encoded(b"not the password of any account", &salt, &argon2::Config::default())
+        .expect("Failed to hash the dummy password")
+});
+
+pub async fn register(store: Store, account: Account) -> Result<impl Reply, Rejection> {
+    let mut errors = Vec::new();
+
+    if !account.email.contains('@') || account.email.len() > 255 {
+        errors.push(FieldError::new("email", "is not a valid address"));
+    }
+
+    if account.password.chars().count() < MIN_PASSWORD_LENGTH {
+        errors.push(FieldError::new(
+            "password",
+            &format!("must be at least {} characters long", MIN_PASSWORD_LENGTH),
+        ));
+    }
+
+    if !errors.is_empty() {
+        return Err(warp::reject::custom(Error::ValidationError(errors)));
+    }
+
+    let account = Account {
+        id: None,
+        email: account.email,
+        password: hash_password(account.password).await?,
+        role: Role::User,
+    };
+
+    match store.add_account(account).await {
+        Ok(_) => Ok(warp::reply::with_status("Account added", StatusCode::OK)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+pub async fn login(store: Store, auth: AuthConfig, login: Account) -> Result<impl Reply, Rejection> {
+    let account = match store.get_account(&login.email).await {
+        Ok(account) => account,
+        Err(e) => return Err(warp::reject::custom(e)),
+    };
+
+    // Unknown addresses get the same answer as wrong passwords
+    let hash = account.as_ref().map(|account| account.password.clone());
+    match (verify_password(hash, login.password).await, account) {
+        (Ok(true), Some(account)) => {
+            event!(target: "rwd", Level::INFO, "{} logged in", account.email);
+
+            Ok(warp::reply::json(&issue_token(&account, &auth)))
+        }
+        (Ok(_), _) => Err(warp::reject::custom(Error::WrongPassword)),
+        (Err(e), _) => Err(warp::reject::custom(Error::ArgonLibraryError(e))),
+    }
+}
+
+/// Filter which requires a valid bearer token
+/// and passes the session it was issued for to the handler
+pub fn auth(auth: AuthConfig) -> impl Filter<Extract = (Session,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("Authorization").and_then(move |token: Option<String>| {
+        let result = match token {
+            Some(token) => verify_token(&token, &auth.token_secret),
+            None => Err(Error::Unauthorized),
+        };
+
+        async move { result.map_err(warp::reject::custom) }
+    })
+}
+
+/// Argon2 is slow on purpose, so it runs on the blocking thread pool
+async fn hash_password(password: String) -> Result<String, Rejection> {
+    tokio::task::spawn_blocking(move || {
+        let salt = rand::thread_rng().r#gen::<[u8; 32]>();
+        let config = argon2::Config::default();
+
+        argon2::hash_encoded(password.as_bytes(), &salt, &config)
+    })
+    .await
+    .expect("Password hashing panicked")
+    .map_err(|e| warp::reject::custom(Error::ArgonLibraryError(e)))
+}
+
+/// Verifies against the dummy hash when there is no stored one
+async fn verify_password(hash: Option<String>, password: String) -> Result<bool, argon2::Error> {
+    tokio::task::spawn_blocking(move || {
+        let hash = hash.as_deref().unwrap_or(&DUMMY_HASH);
+        argon2::verify_encoded(hash, password.as_bytes())
+    })
+    .await
+    .expect("Password verification panicked")
+}
+
+/// Signs a token for the account, role changes apply on the next login
+fn issue_token(account: &Account, auth: &AuthConfig) -> String {
+    let now = Utc::now();
+
+    let session = Session {
+        exp: (now + Duration::minutes(auth.token_ttl_minutes)).timestamp(),
+        iat: now.timestamp(),
+        account_id: account.id.clone().expect("stored account without id"),
+        role: account.role,
+    };
+
+    // Signing with an HMAC secret cannot fail
+    encode(
+        &Header::default(),
+        &session,
+        &EncodingKey::from_secret(auth.token_secret.as_bytes()),
+    )
+    .expect("Failed to sign token")
+}
+
+fn verify_token(token: &str, secret: &str) -> Result<Session, Error> {
+    let token = token.strip_prefix("Bearer ").unwrap_or(token);
+
+    decode::<Session>(
+        token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &Validation::default(),
+    )
+    .map(|data| data.claims)
+    .map_err(|_| Error::CannotDecryptToken)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Policy;
+    use crate::routes::testing::{app, body, request};
+    use crate::types::account::Role;
+
+    use serde_json::json;
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    async fn posting_requires_a_valid_token() {
+        let app = app(Policy::Censor);
+
+        let question = json!({ "title": "A title", "content": "Some content" });
+        let anonymous = app
+            .send(request("POST", "/questions", None).json(&question))
+            .await;
+        assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+
+        let forged = app.ask("Bearer not.a.token", "A title", "Some content").await;
+        assert_eq!(forged.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(body(&forged)["code"], "invalid_token");
+
+        let token = app.sign_up("ann@example.com", Role::User).await;
+        let asked = app.ask(&token, "A title", "Some content").await;
+        assert_eq!(asked.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn login_rejects_wrong_credentials() {
+        let app = app(Policy::Censor);
+        app.sign_up("ann@example.com", Role::User).await;
+
+        // An unknown address gets the same answer as a wrong password
+        for (email, password) in [
+            ("ann@example.com", "wrong password"),
+            ("bob@example.com", "long enough"),
+        ] {
+            let credentials = json!({ "email": email, "password": password });
+            let login = app
+                .send(request("POST", "/login", None).json(&credentials))
+                .await;
+            assert_eq!(login.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(body(&login)["code"], "invalid_credentials");
+        }
+
+        let short = json!({ "email": "bob@example.com", "password": "short" });
+        let registered = app
+            .send(request("POST", "/registration", None).json(&short))
+            .await;
+        assert_eq!(registered.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::profanity::{Screen, Verdict};
+use crate::store::Store;
+use crate::types::account::Session;
+use crate::types::answer::AnswerId;
+use crate::types::comment::{CommentTarget, NewComment};
+use crate::types::question::QuestionId;
+
+use handle_errors::{Error, FieldError};
+
+use warp::{Rejection, Reply, http::StatusCode};
+
+use tracing::{Level, event};
+
+/// Comments longer than this are rejected, longer remarks belong in an answer
+const MAX_COMMENT_LENGTH: usize = 600;
+
+pub async fn get_question_comments(id: i32, store: Store) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "querying comments of question {}", id);
+
+    get_comments(CommentTarget::Question(QuestionId(id)), store).await
+}
+
+pub async fn add_question_comment(
+    id: i32,
+    session: Session,
+    store: Store,
+    profanity: Screen,
+    comment: NewComment,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} commenting on question {}", session.account_id, id);
+
+    add_comment(CommentTarget::Question(QuestionId(id)), session, store, profanity, comment).await
+}
+
+pub async fn get_answer_comments(id: i32, store: Store) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "querying comments of answer {}", id);
+
+    get_comments(CommentTarget::Answer(AnswerId(id)), store).await
+}
+
+pub async fn add_answer_comment(
+    id: i32,
+    session: Session,
+    store: Store,
+    profanity: Screen,
+    comment: NewComment,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} commenting on answer {}", session.account_id, id);
+
+    add_comment(CommentTarget::Answer(AnswerId(id)), session, store, profanity, comment).await
+}
+
+pub async fn delete_comment(
+    id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} deleting comment {}", session.account_id, id);
+
+    if !session.is_moderator() && !store.is_comment_owner(id, &session.account_id).await? {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    match store.delete_comment(id).await {
+        Ok(_) => Ok(warp::reply::with_status(
+            format!("Comment {} deleted", id),
+            StatusCode::OK,
+        )),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+async fn get_comments(target: CommentTarget, store: Store) -> Result<impl Reply, Rejection> {
+    match store.get_comments(&target).await {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Checks the length of a comment and runs it through the same
+/// profanity policy as answers before storing it
+async fn add_comment(
+    target: CommentTarget,
+    session: Session,
+    store: Store,
+    profanity: Screen,
+    comment: NewComment,
+) -> Result<impl Reply, Rejection> {
+    let length = comment.content.trim().chars().count();
+
+    if length == 0 {
+        return Err(warp::reject::custom(Error::ValidationError(vec![FieldError::new(
+            "content",
+            "must not be empty",
+        )])));
+    }
+
+    if length > MAX_COMMENT_LENGTH {
+        return Err(warp::reject::custom(Error::ValidationError(vec![FieldError::new(
+            "content",
+            &format!("must be at most {} characters long", MAX_COMMENT_LENGTH),
+        )])));
+    }
+
+    let content = profanity
+        .check("content", comment.content.trim().to_string())
+        .await?;
+
+    // Comments cannot be hidden, the ones the policy would hold are rejected instead
+    if let Verdict::Hold(_) = profanity.verdict(&[&content])? {
+        return Err(warp::reject::custom(Screen::rejection(&[&content])));
+    }
+
+    match store
+        .add_comment(
+            &target,
+            NewComment {
+                content: content.content,
+            },
+            &session.account_id,
+        )
+        .await
+    {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Policy;
+    use crate::routes::testing::{app, body, request};
+    use crate::types::account::Role;
+
+    use serde_json::json;
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    async fn comments_the_policy_would_hold_are_rejected() {
+        let app = app(Policy::Hold);
+        let ann = app.sign_up("ann@example.com", Role::User).await;
+        app.ask(&ann, "A title", "Some content").await;
+
+        let comment = app
+            .send(
+                request("POST", "/questions/1/comments", Some(&ann))
+                    .json(&json!({ "content": "you shit" })),
+            )
+            .await;
+        assert_eq!(comment.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(body(&comment)["code"], "profane_content");
+        assert!(app.store.comments.read().is_empty());
+
+        let comment = app
+            .send(
+                request("POST", "/questions/1/comments", Some(&ann))
+                    .json(&json!({ "content": "thanks" })),
+            )
+            .await;
+        assert_eq!(comment.status(), StatusCode::OK);
+        assert_eq!(app.store.comments.read().len(), 1);
+    }
+}
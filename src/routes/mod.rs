@@ -1,2 +1,563 @@
 pub mod answer;
+pub mod authentication;
+pub mod comment;
+pub mod moderation;
+pub mod pagination;
 pub mod question;
+pub mod search;
+pub mod tag;
+
+#[cfg(test)]
+mod testing;
+
+use crate::config::{AuthConfig, PaginationConfig};
+use crate::profanity::Screen;
+use crate::store::Store;
+
+use answer::*;
+use authentication::{auth, login, register};
+use comment::*;
+use moderation::*;
+use question::*;
+use search::search;
+use tag::*;
+
+use handle_errors::{REQUEST_ID_HEADER, request_id, return_error};
+use warp::{Filter, Rejection, Reply, http::Method, reply::Response};
+
+/// Every route of the service, with CORS and the problem responses of failed requests
+pub fn routes(
+    auth_config: AuthConfig,
+    pagination: PaginationConfig,
+    store: Store,
+    profanity: Screen,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let statics = warp::fs::dir("statics");
+
+    let store_filter = warp::any().map(move || store.clone());
+    let profanity_filter = warp::any().map(move || profanity.clone());
+    let pagination_filter = warp::any().map(move || pagination.clone());
+
+    // used for log4rs
+    // let id_filter = warp::any().map(|| uuid::Uuid::new_v4().to_string());
+
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_header("content-type")
+        .allow_header("authorization")
+        //.allow_header("not-in-the-request")
+        .allow_methods(&[Method::PUT, Method::DELETE, Method::GET, Method::POST])
+        .expose_headers(vec!["link", "x-total-count", REQUEST_ID_HEADER]);
+
+    let get_questions = warp::get()
+        .and(warp::path("questions"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(pagination_filter.clone())
+        .and(store_filter.clone())
+        //.and(id_filter)
+        .and_then(get_questions)
+        .with(warp::trace(|info| {
+            tracing::info_span!(
+            "get_questions request",
+            method = %info.method(),
+            path = %info.path(),
+            id = %uuid::Uuid::new_v4(),
+            )
+        }));
+
+    let get_question = warp::get()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(store_filter.clone())
+        .and_then(get_question);
+
+    let add_question = warp::post()
+        .and(warp::path("questions"))
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and(profanity_filter.clone())
+        .and(warp::body::json())
+        .and_then(add_question);
+
+    let update_question = warp::put()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and(profanity_filter.clone())
+        .and(warp::body::json())
+        .and_then(update_question);
+
+    let delete_question = warp::delete()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and_then(delete_question);
+
+    let add_answer = warp::post()
+        .and(warp::path("answers"))
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(warp::body::form())
+        .and(store_filter.clone())
+        .and(profanity_filter.clone())
+        .and_then(add_answer);
+
+    let get_answers = warp::get()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("answers"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(pagination_filter.clone())
+        .and(store_filter.clone())
+        .and_then(get_answers);
+
+    let get_answer = warp::get()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and_then(get_answer);
+
+    let update_answer = warp::put()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and(profanity_filter.clone())
+        .and(warp::body::json())
+        .and_then(update_answer);
+
+    let delete_answer = warp::delete()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and_then(delete_answer);
+
+    let restore_question = warp::post()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("restore"))
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and_then(restore_question);
+
+    let purge_question = warp::delete()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("purge"))
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and_then(purge_question);
+
+    let restore_answer = warp::post()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("restore"))
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and_then(restore_answer);
+
+    let vote_question = warp::post()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("vote"))
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and(warp::body::json())
+        .and_then(vote_question);
+
+    let vote_answer = warp::post()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("vote"))
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and(warp::body::json())
+        .and_then(vote_answer);
+
+    let accept_answer = warp::post()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("accept"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and_then(accept_answer);
+
+    let get_question_comments = warp::get()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("comments"))
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and_then(get_question_comments);
+
+    let add_question_comment = warp::post()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("comments"))
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and(profanity_filter.clone())
+        .and(warp::body::json())
+        .and_then(add_question_comment);
+
+    let get_answer_comments = warp::get()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("comments"))
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and_then(get_answer_comments);
+
+    let add_answer_comment = warp::post()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("comments"))
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and(profanity_filter.clone())
+        .and(warp::body::json())
+        .and_then(add_answer_comment);
+
+    let delete_comment = warp::delete()
+        .and(warp::path("comments"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and_then(delete_comment);
+
+    let get_question_revisions = warp::get()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("revisions"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(pagination_filter.clone())
+        .and(store_filter.clone())
+        .and_then(get_question_revisions);
+
+    let rollback_question = warp::post()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("rollback"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and_then(rollback_question);
+
+    let get_answer_revisions = warp::get()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("revisions"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(pagination_filter.clone())
+        .and(store_filter.clone())
+        .and_then(get_answer_revisions);
+
+    let rollback_answer = warp::post()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("rollback"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and_then(rollback_answer);
+
+    let search = warp::get()
+        .and(warp::path("search"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(pagination_filter.clone())
+        .and(store_filter.clone())
+        .and_then(search);
+
+    let get_tags = warp::get()
+        .and(warp::path("tags"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(pagination_filter.clone())
+        .and(store_filter.clone())
+        .and_then(get_tags);
+
+    let get_tag = warp::get()
+        .and(warp::path("tags"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and_then(get_tag);
+
+    let get_tag_questions = warp::get()
+        .and(warp::path("tags"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("questions"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(pagination_filter.clone())
+        .and(store_filter.clone())
+        .and_then(get_tag_questions);
+
+    let update_tag = warp::put()
+        .and(warp::path("tags"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and(warp::body::json())
+        .and_then(update_tag);
+
+    let get_tag_synonyms = warp::get()
+        .and(warp::path("tags"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("synonyms"))
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and_then(get_tag_synonyms);
+
+    let add_tag_synonym = warp::post()
+        .and(warp::path("tags"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("synonyms"))
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and(warp::body::json())
+        .and_then(add_tag_synonym);
+
+    let delete_tag_synonym = warp::delete()
+        .and(warp::path("tags"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("synonyms"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and_then(delete_tag_synonym);
+
+    let merge_tags = warp::post()
+        .and(warp::path("tags"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("merge"))
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and(warp::body::json())
+        .and_then(merge_tags);
+
+    let get_tag_merges = warp::get()
+        .and(warp::path("tag-merges"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(pagination_filter.clone())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and_then(get_tag_merges);
+
+    let flag_question = warp::post()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("flag"))
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and(warp::body::json())
+        .and_then(flag_question);
+
+    let flag_answer = warp::post()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("flag"))
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and(warp::body::json())
+        .and_then(flag_answer);
+
+    let get_moderation_queue = warp::get()
+        .and(warp::path("moderation"))
+        .and(warp::path("queue"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(pagination_filter.clone())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and_then(get_moderation_queue);
+
+    let moderate_question = warp::post()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("moderate"))
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and(warp::body::json())
+        .and_then(moderate_question);
+
+    let moderate_answer = warp::post()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("moderate"))
+        .and(warp::path::end())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and(warp::body::json())
+        .and_then(moderate_answer);
+
+    let get_moderation_log = warp::get()
+        .and(warp::path("moderation"))
+        .and(warp::path("log"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(pagination_filter.clone())
+        .and(auth(auth_config.clone()))
+        .and(store_filter.clone())
+        .and_then(get_moderation_log);
+
+    let registration = warp::post()
+        .and(warp::path("registration"))
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and(warp::body::json())
+        .and_then(register);
+
+    let login = warp::post()
+        .and(warp::path("login"))
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and(warp::any().map(move || auth_config.clone()))
+        .and(warp::body::json())
+        .and_then(login);
+
+    // Each group is boxed, one chain of every route
+    // nests its type deeper than the compiler allows
+    let question_routes = get_questions
+        .or(get_question)
+        .or(add_question)
+        .or(update_question)
+        .or(delete_question)
+        .or(restore_question)
+        .or(purge_question)
+        .or(vote_question)
+        .or(accept_answer)
+        .or(get_question_revisions)
+        .or(rollback_question)
+        .boxed();
+
+    let answer_routes = add_answer
+        .or(get_answers)
+        .or(get_answer)
+        .or(update_answer)
+        .or(delete_answer)
+        .or(restore_answer)
+        .or(vote_answer)
+        .or(get_answer_revisions)
+        .or(rollback_answer)
+        .boxed();
+
+    let comment_routes = get_question_comments
+        .or(add_question_comment)
+        .or(get_answer_comments)
+        .or(add_answer_comment)
+        .or(delete_comment)
+        .boxed();
+
+    let tag_routes = get_tags
+        .or(get_tag)
+        .or(get_tag_questions)
+        .or(update_tag)
+        .or(get_tag_synonyms)
+        .or(add_tag_synonym)
+        .or(delete_tag_synonym)
+        .or(merge_tags)
+        .or(get_tag_merges)
+        .boxed();
+
+    let moderation_routes = flag_question
+        .or(flag_answer)
+        .or(get_moderation_queue)
+        .or(moderate_question)
+        .or(moderate_answer)
+        .or(get_moderation_log)
+        .boxed();
+
+    let routes = statics
+        .or(question_routes)
+        .or(answer_routes)
+        .or(comment_routes)
+        .or(tag_routes)
+        .or(moderation_routes)
+        .or(search)
+        .or(registration)
+        .or(login)
+        .with(cors)
+        //.with(log)
+        //.with(warp::trace::request())
+        .map(|reply| Ok(Reply::into_response(reply)))
+        .or_else(|rejection| async move { Ok::<_, Rejection>((Err(rejection),)) });
+
+    // Every reply carries the id of the request, problems in their body as well
+    request_id()
+        .and(routes)
+        .and_then(|request_id: String, result: Result<Response, Rejection>| async move {
+            let reply = match result {
+                Ok(reply) => reply,
+                Err(rejection) => return_error(request_id.clone(), rejection).await?.into_response(),
+            };
+
+            Ok::<_, Rejection>(warp::reply::with_header(reply, REQUEST_ID_HEADER, request_id))
+        })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::testing::{app, body, request};
+    use crate::config::Policy;
+
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    async fn problems_carry_the_id_of_the_request() {
+        let app = app(Policy::Censor);
+
+        let sent = app
+            .send(request("GET", "/questions/1", None).header("x-request-id", "client-chosen-id"))
+            .await;
+        assert_eq!(sent.status(), StatusCode::NOT_FOUND);
+        assert_eq!(sent.headers()["x-request-id"], "client-chosen-id");
+        assert_eq!(body(&sent)["request_id"], "client-chosen-id");
+
+        let made_up = app.send(request("GET", "/questions/1", None)).await;
+        let request_id = made_up.headers()["x-request-id"].to_str().unwrap();
+        assert_eq!(request_id.len(), 36);
+        assert_eq!(body(&made_up)["request_id"], request_id);
+
+        let found = app.send(request("GET", "/questions", None)).await;
+        assert_eq!(found.status(), StatusCode::OK);
+        assert!(found.headers().contains_key("x-request-id"));
+    }
+}
@@ -0,0 +1,228 @@
+use crate::config::PaginationConfig;
+use crate::store::Store;
+use crate::types::account::Session;
+use crate::types::answer::AnswerId;
+use crate::types::moderation::{FlagTarget, NewFlag, NewModerationAction};
+use crate::types::pagination::{extract_pagination, reject_unknown_params};
+use crate::types::question::QuestionId;
+
+use handle_errors::{Error, FieldError};
+
+use std::collections::HashMap;
+
+use warp::{Rejection, Reply};
+
+use tracing::{Level, event};
+
+/// Longest explanation accepted with a flag or a moderator action
+const MAX_NOTE_LENGTH: usize = 500;
+
+pub async fn flag_question(
+    id: i32,
+    session: Session,
+    store: Store,
+    flag: NewFlag,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} flagging question {}", session.account_id, id);
+
+    add_flag(FlagTarget::Question(QuestionId(id)), session, store, flag).await
+}
+
+pub async fn flag_answer(
+    id: i32,
+    session: Session,
+    store: Store,
+    flag: NewFlag,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} flagging answer {}", session.account_id, id);
+
+    add_flag(FlagTarget::Answer(AnswerId(id)), session, store, flag).await
+}
+
+/// Lists the posts with open flags, the most flagged first, moderators only
+/// # Example query
+/// `/moderation/queue?limit=20&offset=0`
+pub async fn get_moderation_queue(
+    params: HashMap<String, String>,
+    config: PaginationConfig,
+    session: Session,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} querying the moderation queue", session.account_id);
+
+    if !session.is_moderator() {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    reject_unknown_params(&params, &["limit", "offset"])?;
+    let pagination = extract_pagination(&params, &config)?;
+
+    match store
+        .get_moderation_queue(pagination.limit, pagination.offset)
+        .await
+    {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+pub async fn moderate_question(
+    id: i32,
+    session: Session,
+    store: Store,
+    action: NewModerationAction,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} moderating question {}", session.account_id, id);
+
+    moderate(FlagTarget::Question(QuestionId(id)), session, store, action).await
+}
+
+pub async fn moderate_answer(
+    id: i32,
+    session: Session,
+    store: Store,
+    action: NewModerationAction,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} moderating answer {}", session.account_id, id);
+
+    moderate(FlagTarget::Answer(AnswerId(id)), session, store, action).await
+}
+
+/// Lists the recorded moderator actions, newest first, moderators only
+pub async fn get_moderation_log(
+    params: HashMap<String, String>,
+    config: PaginationConfig,
+    session: Session,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} querying the moderation log", session.account_id);
+
+    if !session.is_moderator() {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    reject_unknown_params(&params, &["limit", "offset"])?;
+    let pagination = extract_pagination(&params, &config)?;
+
+    match store
+        .get_moderation_log(pagination.limit, pagination.offset)
+        .await
+    {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+async fn add_flag(
+    target: FlagTarget,
+    session: Session,
+    store: Store,
+    flag: NewFlag,
+) -> Result<impl Reply, Rejection> {
+    let flag = NewFlag {
+        details: check_note("details", flag.details)?,
+        ..flag
+    };
+
+    match store.add_flag(&target, flag, &session.account_id).await {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Dismisses, hides or deletes a post and resolves its open flags,
+/// moderators only
+async fn moderate(
+    target: FlagTarget,
+    session: Session,
+    store: Store,
+    action: NewModerationAction,
+) -> Result<impl Reply, Rejection> {
+    if !session.is_moderator() {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    let action = NewModerationAction {
+        note: check_note("note", action.note)?,
+        ..action
+    };
+
+    match store.moderate(&target, action, &session.account_id).await {
+        Ok(res) => {
+            event!(target: "rwd", Level::INFO, "{:?} on {} resolved flags {:?}", res.action, target, res.flag_ids);
+            Ok(warp::reply::json(&res))
+        }
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Trims an optional explanation, dropping it when blank
+fn check_note(field: &str, note: Option<String>) -> Result<Option<String>, Error> {
+    let note = note
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty());
+
+    match note {
+        Some(note) if note.chars().count() > MAX_NOTE_LENGTH => {
+            Err(Error::ValidationError(vec![FieldError::new(
+                field,
+                &format!("must be at most {} characters long", MAX_NOTE_LENGTH),
+            )]))
+        }
+        note => Ok(note),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Policy;
+    use crate::routes::testing::{app, request};
+    use crate::types::account::Role;
+
+    use serde_json::json;
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    async fn dismissing_leaves_posts_deleted_before_they_were_hidden_deleted() {
+        let app = app(Policy::Censor);
+        let ann = app.sign_up("ann@example.com", Role::User).await;
+        let bob = app.sign_up("bob@example.com", Role::User).await;
+        let moderator = app.sign_up("mod@example.com", Role::Moderator).await;
+        app.ask(&ann, "A title", "Some content").await;
+        app.answer(&bob, 1, "First answer").await;
+        app.answer(&bob, 1, "Second answer").await;
+
+        let moderate = |answer: i32, action: &str| {
+            request("POST", &format!("/answers/{}/moderate", answer), Some(&moderator))
+                .json(&json!({ "action": action }))
+        };
+        let app = &app;
+        let status = |answer: i32| async move {
+            app.send(request("GET", &format!("/answers/{}", answer), None))
+                .await
+                .status()
+        };
+
+        // Hidden while visible, dismissing brings it back
+        assert_eq!(app.send(moderate(1, "hide")).await.status(), StatusCode::OK);
+        assert_eq!(status(1).await, StatusCode::NOT_FOUND);
+        assert_eq!(app.send(moderate(1, "dismiss")).await.status(), StatusCode::OK);
+        assert_eq!(status(1).await, StatusCode::OK);
+
+        // Deleted by its author first, dismissing only takes back the hide
+        app.send(request("DELETE", "/answers/2", Some(&bob))).await;
+        assert_eq!(app.send(moderate(2, "hide")).await.status(), StatusCode::OK);
+        let restored = app
+            .send(request("POST", "/answers/2/restore", Some(&bob)))
+            .await;
+        assert_eq!(restored.status(), StatusCode::FORBIDDEN);
+
+        assert_eq!(app.send(moderate(2, "dismiss")).await.status(), StatusCode::OK);
+        assert_eq!(status(2).await, StatusCode::NOT_FOUND);
+        let restored = app
+            .send(request("POST", "/answers/2/restore", Some(&bob)))
+            .await;
+        assert_eq!(restored.status(), StatusCode::OK);
+        assert_eq!(status(2).await, StatusCode::OK);
+    }
+}
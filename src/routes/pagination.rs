@@ -0,0 +1,124 @@
+use crate::types::pagination::{Page, Pagination};
+
+use serde::Serialize;
+
+use std::collections::{BTreeMap, HashMap};
+
+use warp::Reply;
+use warp::http::HeaderValue;
+use warp::reply::Response;
+
+/// Replies with the items of a page as a JSON array, the size of the whole
+/// listing in `X-Total-Count` and links to the neighbouring pages in `Link`
+/// # Example headers
+/// ```text
+/// X-Total-Count: 42
+/// Link: </questions?after=MTc0NjUyMTIwMDAwMDAwMDo0Mg&limit=10>; rel="next"
+/// ```
+/// `keyset` selects cursor links, listings in an order cursors
+/// cannot follow get offset links instead.
+pub fn paginated_reply<T: Serialize>(
+    path: &str,
+    params: &HashMap<String, String>,
+    pagination: &Pagination,
+    page: Page<T>,
+    keyset: bool,
+) -> Response {
+    // Everything but the position is carried over to the links
+    let query: BTreeMap<&str, &str> = params
+        .iter()
+        .filter(|(key, _)| !matches!(key.as_str(), "after" | "before" | "offset"))
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect();
+
+    let mut links = Vec::new();
+
+    if keyset {
+        if let Some(next) = page.next {
+            links.push(link(path, &query, "after", &next.encode(), "next"));
+        }
+        if let Some(prev) = page.prev {
+            links.push(link(path, &query, "before", &prev.encode(), "prev"));
+        }
+    } else {
+        if page.next.is_some() {
+            let offset = (pagination.offset + pagination.limit).to_string();
+            links.push(link(path, &query, "offset", &offset, "next"));
+        }
+        if page.prev.is_some() {
+            let offset = pagination.offset.saturating_sub(pagination.limit).to_string();
+            links.push(link(path, &query, "offset", &offset, "prev"));
+        }
+    }
+
+    let mut response = warp::reply::json(&page.items).into_response();
+    let headers = response.headers_mut();
+    headers.insert("x-total-count", HeaderValue::from(page.total));
+
+    if !links.is_empty()
+        && let Ok(value) = HeaderValue::from_str(&links.join(", "))
+    {
+        headers.insert("link", value);
+    }
+
+    response
+}
+
+fn link(path: &str, query: &BTreeMap<&str, &str>, key: &str, value: &str, rel: &str) -> String {
+    let mut query = query.clone();
+    query.insert(key, value);
+
+    // Serializing a map of strings cannot fail
+    let query = serde_urlencoded::to_string(&query).unwrap_or_default();
+
+    format!("<{}?{}>; rel=\"{}\"", path, query, rel)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Policy;
+    use crate::routes::testing::{app, ids, request};
+    use crate::types::account::Role;
+
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    async fn questions_are_paginated() {
+        let app = app(Policy::Censor);
+        let token = app.sign_up("ann@example.com", Role::User).await;
+        for i in 1..=5 {
+            app.ask(&token, &format!("Question {}", i), "Some content").await;
+        }
+
+        let first = app.send(request("GET", "/questions", None)).await;
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(first.headers()["x-total-count"], "5");
+        assert_eq!(ids(&first), vec![5, 4]);
+
+        // Follow the `next` link of the first page
+        let link = first.headers()["link"].to_str().unwrap();
+        assert!(link.ends_with("rel=\"next\""), "{}", link);
+        let next = &link[link.find('<').unwrap() + 1..link.find('>').unwrap()];
+
+        let second = app.send(request("GET", next, None)).await;
+        assert_eq!(ids(&second), vec![3, 2]);
+        assert!(second.headers()["link"].to_str().unwrap().contains("rel=\"prev\""));
+
+        let last = app
+            .send(request("GET", "/questions?sort=oldest&limit=3&offset=3", None))
+            .await;
+        assert_eq!(ids(&last), vec![4, 5]);
+    }
+
+    #[tokio::test]
+    async fn invalid_pagination_is_rejected() {
+        let app = app(Policy::Censor);
+
+        for query in ["limit=0", "limit=6", "offset=-1", "after=garbage", "limt=2"] {
+            let response = app
+                .send(request("GET", &format!("/questions?{}", query), None))
+                .await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", query);
+        }
+    }
+}
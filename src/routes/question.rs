@@ -1,9 +1,17 @@
+use crate::config::PaginationConfig;
+use crate::routes::pagination::paginated_reply;
 use crate::store::Store;
-use crate::types::pagination::{Pagination, extract_pagination};
-use crate::types::question::{NewQuestion, Question};
-use crate::profanity::check_profanity;
+use crate::types::account::Session;
+use crate::types::filter::{QUESTION_FILTER_PARAMS, check_cursor_sort, extract_question_filter};
+use crate::types::pagination::{PAGINATION_PARAMS, extract_pagination, reject_unknown_params};
+use crate::types::moderation::FlagTarget;
+use crate::types::question::{NewQuestion, QuestionId, check_post_size};
+use crate::types::revision::question_diffs;
+use crate::types::tag::normalize_tags;
+use crate::types::vote::NewVote;
+use crate::profanity::Screen;
 
-//use handle_errors::Error;
+use handle_errors::{Error, FieldError};
 
 use std::collections::HashMap;
 
@@ -12,92 +20,599 @@ use warp::{Rejection, Reply, http::StatusCode};
 //use log::info;
 use tracing::{Level, event, instrument};
 
-#[instrument]
+#[instrument(skip(store))]
 pub async fn get_questions(
     params: HashMap<String, String>,
+    config: PaginationConfig,
     store: Store,
 ) -> Result<impl Reply, Rejection> {
     event!(target: "rwd", Level::INFO, "querying questions");
 
-    let mut page = Pagination::default();
+    reject_unknown_params(
+        &params,
+        &[QUESTION_FILTER_PARAMS.as_slice(), &PAGINATION_PARAMS].concat(),
+    )?;
 
-    if !params.is_empty() {
-        event!(Level::INFO, pagination = true);
-        page = extract_pagination(params)?;
+    let mut filter = extract_question_filter(&params)?;
+    let pagination = extract_pagination(&params, &config)?;
+
+    check_cursor_sort(&filter, &pagination)?;
+
+    if !filter.tags.is_empty() {
+        filter.tags = store.resolve_tag_synonyms(filter.tags).await?;
     }
 
-    match store.get_questions(page.limit, page.offset).await {
-        Ok(res) => Ok(warp::reply::json(&res)),
+    match store.get_questions(&filter, &pagination).await {
+        Ok(page) => Ok(paginated_reply(
+            "/questions",
+            &params,
+            &pagination,
+            page,
+            filter.sort.is_chronological(),
+        )),
         Err(e) => Err(warp::reject::custom(e)),
     }
 }
 
-pub async fn get_question(id: i32, store: Store) -> Result<impl Reply, Rejection> {
+/// Fetches a question, with `?include=answers` together with its answers
+pub async fn get_question(
+    id: i32,
+    params: HashMap<String, String>,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
     event!(target: "rwd", Level::INFO, "querying question with id: {}", id);
 
-    match store.get_question(id).await {
-        Ok(res) => Ok(warp::reply::json(&res)),
-        Err(e) => Err(warp::reject::custom(e)),
-    }
-}
+    reject_unknown_params(&params, &["include"])?;
 
-pub async fn add_question(store: Store, question: NewQuestion) -> Result<impl Reply, Rejection> {
-    let title = match check_profanity(question.title).await {
-	Ok(res) => res,
-	Err(e) => return Err(warp::reject::custom(e)),
+    let res = match params.get("include").map(String::as_str) {
+        None => store.get_question(id).await.map(|q| warp::reply::json(&q)),
+        Some("answers") => store
+            .get_question_with_answers(id)
+            .await
+            .map(|q| warp::reply::json(&q)),
+        Some(_) => Err(Error::ValidationError(vec![FieldError::new(
+            "include",
+            "must be `answers`",
+        )])),
     };
 
-    let content = match check_profanity(question.content).await {
-	Ok(res) => res,
-	Err(e) => return Err(warp::reject::custom(e)),
-    };
+    res.map_err(warp::reject::custom)
+}
+
+pub async fn add_question(
+    session: Session,
+    store: Store,
+    profanity: Screen,
+    question: NewQuestion,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} adding question", session.account_id);
+
+    check_post_size(Some(&question.title), &question.content)?;
+    let tags = canonical_tags(question.tags, &store).await?;
+
+    let (title, content) = tokio::try_join!(
+        profanity.check("title", question.title),
+        profanity.check("content", question.content),
+    )?;
+    let verdict = profanity.verdict(&[&title, &content])?;
 
     let question = NewQuestion {
-	title, 
-	content,
-	tags: question.tags,
+        title: title.content,
+        content: content.content,
+        tags,
     };
 
-    match store.add_question(question).await {
-        Ok(_) => Ok(warp::reply::with_status("Question added", StatusCode::OK)),
-        Err(e) => Err(warp::reject::custom(e)),
+    let hold = verdict.into_hold();
+    let held = hold.is_some();
+    store
+        .add_question(question, &session.account_id, hold)
+        .await?;
+
+    if held {
+        Ok(warp::reply::with_status(
+            "Question held for review",
+            StatusCode::ACCEPTED,
+        ))
+    } else {
+        Ok(warp::reply::with_status("Question added", StatusCode::OK))
     }
 }
 
 pub async fn update_question(
     id: i32,
+    session: Session,
     store: Store,
-    question: Question,
+    profanity: Screen,
+    question: NewQuestion,
 ) -> Result<impl Reply, Rejection> {
-    let title = match check_profanity(question.title).await {
-        Ok(res) => res,
-        Err(e) => return Err(warp::reject::custom(e)),
-    };
+    event!(target: "rwd", Level::INFO, "account {} updating question {}", session.account_id, id);
+
+    if !session.is_moderator() && !store.is_question_owner(id, &session.account_id).await? {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
 
-    let content = match check_profanity(question.content).await {
-        Ok(res) => res,
-        Err(e) => return Err(warp::reject::custom(e)),
+    check_post_size(Some(&question.title), &question.content)?;
+    let tags = canonical_tags(question.tags, &store).await?;
+
+    let (title, content) = tokio::try_join!(
+        profanity.check("title", question.title),
+        profanity.check("content", question.content),
+    )?;
+    let verdict = profanity.verdict(&[&title, &content])?;
+
+    let question = NewQuestion {
+        title: title.content,
+        content: content.content,
+        tags,
     };
 
-    let question = Question {
-	id: question.id,
-        title,
-        content,
-        tags: question.tags,
+    let hold = verdict.into_hold();
+    let status = if hold.is_some() {
+        StatusCode::ACCEPTED
+    } else {
+        StatusCode::OK
     };
+    let question = store
+        .update_question(question, id, &session.account_id, hold)
+        .await?;
+
+    Ok(warp::reply::with_status(warp::reply::json(&question), status))
+}
+
+pub async fn delete_question(
+    id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} deleting question {}", session.account_id, id);
+
+    if !session.is_moderator() && !store.is_question_owner(id, &session.account_id).await? {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    match store.delete_question(id).await {
+        Ok(_) => Ok(warp::reply::with_status(
+            format!("Question {} deleted", id),
+            StatusCode::OK,
+        )),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Brings back a deleted question
+pub async fn restore_question(
+    id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} restoring question {}", session.account_id, id);
 
-    match store.update_question(question, id).await {
+    // Posts hidden by a moderator stay hidden until a moderator says otherwise
+    if !session.is_moderator()
+        && (!store.is_question_owner(id, &session.account_id).await?
+            || store.is_hidden(&FlagTarget::Question(QuestionId(id))).await?)
+    {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    match store.restore_question(id).await {
         Ok(res) => Ok(warp::reply::json(&res)),
         Err(e) => Err(warp::reject::custom(e)),
     }
 }
 
-pub async fn delete_question(id: i32, store: Store) -> Result<impl Reply, Rejection> {
-    match store.delete_question(id).await {
+/// Votes a question up or down, voting again changes the vote
+pub async fn vote_question(
+    id: i32,
+    session: Session,
+    store: Store,
+    vote: NewVote,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} voting on question {}", session.account_id, id);
+
+    match store.vote_question(id, &session.account_id, vote.vote).await {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Removes a question with all of its answers for good, moderators only
+pub async fn purge_question(
+    id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} purging question {}", session.account_id, id);
+
+    if !session.is_moderator() {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    match store.purge_question(id).await {
         Ok(_) => Ok(warp::reply::with_status(
-            format!("Question {} deleted", id),
+            format!("Question {} purged", id),
             StatusCode::OK,
         )),
         Err(e) => Err(warp::reject::custom(e)),
     }
 }
+
+/// Marks one of the answers as the accepted one, question authors only
+pub async fn accept_answer(
+    id: i32,
+    answer_id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(
+        target: "rwd",
+        Level::INFO,
+        "account {} accepting answer {} of question {}",
+        session.account_id,
+        answer_id,
+        id
+    );
+
+    if !store.is_question_owner(id, &session.account_id).await? {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    let answer = store.get_answer(answer_id).await?;
+    if answer.question_id.0 != id {
+        return Err(warp::reject::custom(Error::ValidationError(vec![FieldError::new(
+            "answer_id",
+            &format!("does not belong to question {}", id),
+        )])));
+    }
+
+    match store.accept_answer(id, answer_id).await {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Lists the versions of a question, oldest first, with the changes each one made
+/// # Example query
+/// `/questions/1/revisions?limit=10&offset=10`
+pub async fn get_question_revisions(
+    id: i32,
+    params: HashMap<String, String>,
+    config: PaginationConfig,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "querying revisions of question {}", id);
+
+    reject_unknown_params(&params, &["limit", "offset"])?;
+    let pagination = extract_pagination(&params, &config)?;
+
+    // The revision before the page is only fetched to diff the first one against
+    let before = pagination.offset.min(1);
+
+    match store
+        .get_question_revisions(id, pagination.limit + before, pagination.offset - before)
+        .await
+    {
+        Ok(revisions) => {
+            let diffs: Vec<_> = question_diffs(revisions)
+                .into_iter()
+                .skip(before as usize)
+                .collect();
+            Ok(warp::reply::json(&diffs))
+        }
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Restores an earlier version of a question by recording it as a new revision
+pub async fn rollback_question(
+    id: i32,
+    revision: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(
+        target: "rwd",
+        Level::INFO,
+        "account {} rolling question {} back to revision {}",
+        session.account_id,
+        id,
+        revision
+    );
+
+    if !session.is_moderator() && !store.is_question_owner(id, &session.account_id).await? {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    // The content of a revision went through the profanity filter already,
+    // its tags may predate normalization or name merged tags though
+    let revision = store.get_question_revision(id, revision).await?;
+    let question = NewQuestion {
+        title: revision.title,
+        content: revision.content,
+        tags: canonical_tags(revision.tags, &store).await?,
+    };
+
+    match store
+        .update_question(question, id, &session.account_id, None)
+        .await
+    {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Normalizes the tags of a question and replaces synonyms with their tags
+async fn canonical_tags(
+    tags: Option<Vec<String>>,
+    store: &Store,
+) -> Result<Option<Vec<String>>, Error> {
+    match normalize_tags(tags)? {
+        Some(tags) => Ok(Some(store.resolve_tag_synonyms(tags).await?)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Policy;
+    use crate::routes::testing::{app, body, request};
+    use crate::types::account::Role;
+
+    use serde_json::json;
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    async fn only_owners_and_moderators_edit_questions() {
+        let app = app(Policy::Censor);
+        let ann = app.sign_up("ann@example.com", Role::User).await;
+        let bob = app.sign_up("bob@example.com", Role::User).await;
+        let moderator = app.sign_up("mod@example.com", Role::Moderator).await;
+        app.ask(&ann, "A title", "Some content").await;
+
+        let edit = json!({ "title": "Another title", "content": "Other content" });
+        for (token, status) in [
+            (&bob, StatusCode::FORBIDDEN),
+            (&ann, StatusCode::OK),
+            (&moderator, StatusCode::OK),
+        ] {
+            let edited = app
+                .send(request("PUT", "/questions/1", Some(token)).json(&edit))
+                .await;
+            assert_eq!(edited.status(), status);
+        }
+
+        let deleted = app
+            .send(request("DELETE", "/questions/1", Some(&bob)))
+            .await;
+        assert_eq!(deleted.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn questions_come_with_their_answers_on_request() {
+        let app = app(Policy::Censor);
+        let ann = app.sign_up("ann@example.com", Role::User).await;
+        let bob = app.sign_up("bob@example.com", Role::User).await;
+        app.ask(&ann, "A title", "Some content").await;
+        app.ask(&ann, "Another title", "Other content").await;
+        for content in ["First answer", "Second answer"] {
+            let answered = app.answer(&bob, 1, content).await;
+            assert_eq!(answered.status(), StatusCode::OK);
+        }
+
+        let listed = body(&app.send(request("GET", "/questions?sort=oldest", None)).await);
+        assert_eq!(listed[0]["answer_count"], 2);
+        assert_eq!(listed[1]["answer_count"], 0);
+
+        let plain = body(&app.send(request("GET", "/questions/1", None)).await);
+        assert!(plain.get("answers").is_none());
+
+        let fetched = app
+            .send(request("GET", "/questions/1?include=answers", None))
+            .await;
+        assert_eq!(fetched.status(), StatusCode::OK);
+        let fetched = body(&fetched);
+        assert_eq!(fetched["title"], "A title");
+        assert_eq!(fetched["answer_count"], 2);
+        assert_eq!(fetched["answers"][0]["content"], "First answer");
+        assert_eq!(fetched["answers"][1]["content"], "Second answer");
+
+        let unknown = app
+            .send(request("GET", "/questions/1?include=comments", None))
+            .await;
+        assert_eq!(unknown.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(body(&unknown)["errors"][0]["field"], "include");
+
+        let missing = app
+            .send(request("GET", "/questions/3?include=answers", None))
+            .await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn edits_are_numbered_and_diffed() {
+        let app = app(Policy::Censor);
+        let ann = app.sign_up("ann@example.com", Role::User).await;
+        app.ask(&ann, "A title", "Some content").await;
+        for content in ["Some content\nMore content", "More content"] {
+            let edit = json!({ "title": "A title", "content": content });
+            let edited = app
+                .send(request("PUT", "/questions/1", Some(&ann)).json(&edit))
+                .await;
+            assert_eq!(edited.status(), StatusCode::OK);
+        }
+
+        let revisions = app
+            .send(request("GET", "/questions/1/revisions?limit=5", None))
+            .await;
+        let revisions = body(&revisions);
+        let numbers: Vec<i64> = (0..3).map(|i| revisions[i]["revision"].as_i64().unwrap()).collect();
+        assert_eq!(numbers, vec![1, 2, 3]);
+        assert_eq!(
+            revisions[2]["content_diff"],
+            json!([
+                { "kind": "removed", "line": "Some content" },
+                { "kind": "unchanged", "line": "More content" },
+            ])
+        );
+
+        // A later page is still diffed against the revision before it
+        let second = app
+            .send(request("GET", "/questions/1/revisions?limit=1&offset=1", None))
+            .await;
+        assert_eq!(
+            body(&second)[0]["content_diff"],
+            json!([
+                { "kind": "unchanged", "line": "Some content" },
+                { "kind": "added", "line": "More content" },
+            ])
+        );
+
+        let rolled_back = app
+            .send(request("POST", "/questions/1/rollback/1", Some(&ann)))
+            .await;
+        assert_eq!(rolled_back.status(), StatusCode::OK);
+        let latest = app
+            .send(request("GET", "/questions/1/revisions?offset=3", None))
+            .await;
+        assert_eq!(body(&latest)[0]["revision"], 4);
+        assert_eq!(body(&latest)[0]["content"], "Some content");
+
+        let missing = app
+            .send(request("GET", "/questions/2/revisions", None))
+            .await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn deleted_questions_can_be_restored_until_purged() {
+        let app = app(Policy::Censor);
+        let ann = app.sign_up("ann@example.com", Role::User).await;
+        let bob = app.sign_up("bob@example.com", Role::User).await;
+        let moderator = app.sign_up("mod@example.com", Role::Moderator).await;
+        app.ask(&ann, "A title", "Some content").await;
+        app.answer(&bob, 1, "An answer").await;
+
+        let deleted = app
+            .send(request("DELETE", "/questions/1", Some(&ann)))
+            .await;
+        assert_eq!(deleted.status(), StatusCode::OK);
+        let fetched = app.send(request("GET", "/questions/1", None)).await;
+        assert_eq!(fetched.status(), StatusCode::NOT_FOUND);
+        let listed = app.send(request("GET", "/questions", None)).await;
+        assert_eq!(listed.headers()["x-total-count"], "0");
+
+        let restored = app
+            .send(request("POST", "/questions/1/restore", Some(&bob)))
+            .await;
+        assert_eq!(restored.status(), StatusCode::FORBIDDEN);
+        let restored = app
+            .send(request("POST", "/questions/1/restore", Some(&ann)))
+            .await;
+        assert_eq!(restored.status(), StatusCode::OK);
+        let fetched = app
+            .send(request("GET", "/questions/1?include=answers", None))
+            .await;
+        assert_eq!(body(&fetched)["answer_count"], 1);
+
+        let purged = app
+            .send(request("DELETE", "/questions/1/purge", Some(&ann)))
+            .await;
+        assert_eq!(purged.status(), StatusCode::FORBIDDEN);
+        let purged = app
+            .send(request("DELETE", "/questions/1/purge", Some(&moderator)))
+            .await;
+        assert_eq!(purged.status(), StatusCode::OK);
+        assert!(app.store.answers.read().is_empty());
+
+        let restored = app
+            .send(request("POST", "/questions/1/restore", Some(&moderator)))
+            .await;
+        assert_eq!(restored.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn voting_again_replaces_the_vote() {
+        let app = app(Policy::Censor);
+        let ann = app.sign_up("ann@example.com", Role::User).await;
+        let bob = app.sign_up("bob@example.com", Role::User).await;
+        app.ask(&ann, "A title", "Some content").await;
+
+        let vote = |token: &str, vote: &str| {
+            request("POST", "/questions/1/vote", Some(token)).json(&json!({ "vote": vote }))
+        };
+
+        let voted = app.send(vote(&ann, "up")).await;
+        assert_eq!(body(&voted)["score"], 1);
+        let voted = app.send(vote(&bob, "up")).await;
+        assert_eq!(body(&voted)["score"], 2);
+        let voted = app.send(vote(&bob, "down")).await;
+        assert_eq!(body(&voted)["score"], 0);
+
+        let sideways = app.send(vote(&bob, "sideways")).await;
+        assert_eq!(sideways.status(), StatusCode::BAD_REQUEST);
+        let missing = app
+            .send(request("POST", "/questions/2/vote", Some(&bob)).json(&json!({ "vote": "up" })))
+            .await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+        let fetched = app.send(request("GET", "/questions/1", None)).await;
+        assert_eq!(body(&fetched)["score"], 0);
+    }
+
+    #[tokio::test]
+    async fn held_questions_wait_for_a_moderator() {
+        let app = app(Policy::Hold);
+        let ann = app.sign_up("ann@example.com", Role::User).await;
+        let moderator = app.sign_up("mod@example.com", Role::Moderator).await;
+
+        let asked = app.ask(&ann, "A title", "What the shit").await;
+        assert_eq!(asked.status(), StatusCode::ACCEPTED);
+
+        // Out of sight for everyone, the author cannot bring it back
+        let listed = app.send(request("GET", "/questions", None)).await;
+        assert_eq!(listed.headers()["x-total-count"], "0");
+        let fetched = app.send(request("GET", "/questions/1", None)).await;
+        assert_eq!(fetched.status(), StatusCode::NOT_FOUND);
+        let restored = app
+            .send(request("POST", "/questions/1/restore", Some(&ann)))
+            .await;
+        assert_eq!(restored.status(), StatusCode::FORBIDDEN);
+
+        let queue = app
+            .send(request("GET", "/moderation/queue", Some(&moderator)))
+            .await;
+        let queue = body(&queue);
+        assert_eq!(queue[0]["question_id"], 1);
+        assert_eq!(queue[0]["hidden"], true);
+        assert_eq!(queue[0]["content"], "What the ****");
+
+        let dismissed = app
+            .send(
+                request("POST", "/questions/1/moderate", Some(&moderator))
+                    .json(&json!({ "action": "dismiss" })),
+            )
+            .await;
+        assert_eq!(dismissed.status(), StatusCode::OK);
+
+        let fetched = app.send(request("GET", "/questions/1", None)).await;
+        assert_eq!(fetched.status(), StatusCode::OK);
+        assert_eq!(body(&fetched)["content"], "What the ****");
+    }
+
+    #[tokio::test]
+    async fn held_edits_hide_the_question() {
+        let app = app(Policy::Hold);
+        let ann = app.sign_up("ann@example.com", Role::User).await;
+        app.ask(&ann, "A title", "Some content").await;
+
+        let edit = json!({ "title": "A title", "content": "Some shit" });
+        let edited = app
+            .send(request("PUT", "/questions/1", Some(&ann)).json(&edit))
+            .await;
+        assert_eq!(edited.status(), StatusCode::ACCEPTED);
+
+        let fetched = app.send(request("GET", "/questions/1", None)).await;
+        assert_eq!(fetched.status(), StatusCode::NOT_FOUND);
+        assert_eq!(app.store.flags.read().len(), 1);
+    }
+}
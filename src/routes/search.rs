@@ -0,0 +1,80 @@
+use crate::config::PaginationConfig;
+use crate::store::Store;
+use crate::types::pagination::{extract_pagination, reject_unknown_params};
+
+use handle_errors::{Error, FieldError};
+
+use std::collections::HashMap;
+
+use warp::{Rejection, Reply};
+
+use tracing::{Level, event, instrument};
+
+/// Full-text search over questions and their answers
+/// # Example query
+/// `/search?q=borrow checker&limit=10&offset=0`
+#[instrument(skip(store))]
+pub async fn search(
+    params: HashMap<String, String>,
+    config: PaginationConfig,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    // Results are ordered by rank, which cursors cannot follow
+    reject_unknown_params(&params, &["q", "limit", "offset"])?;
+
+    let query = match params.get("q") {
+        Some(query) if !query.trim().is_empty() => query,
+        _ => {
+            return Err(warp::reject::custom(Error::ValidationError(vec![
+                FieldError::new("q", "a search term is required"),
+            ])));
+        }
+    };
+
+    event!(target: "rwd", Level::INFO, "searching questions for {:?}", query);
+
+    let pagination = extract_pagination(&params, &config)?;
+
+    match store
+        .search_questions(query, pagination.limit, pagination.offset)
+        .await
+    {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Policy;
+    use crate::routes::testing::{app, body, request};
+    use crate::types::account::Role;
+
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    async fn snippets_are_escaped_and_only_mark_matches() {
+        let app = app(Policy::Censor);
+        let ann = app.sign_up("ann@example.com", Role::User).await;
+        app.ask(
+            &ann,
+            "Borrowing in loops",
+            "<script>alert(1)</script> the \u{2}fake\u{3} mark around the borrow checker",
+        )
+        .await;
+
+        let found = app.send(request("GET", "/search?q=borrow", None)).await;
+        assert_eq!(found.status(), StatusCode::OK);
+
+        let snippet = body(&found)[0]["snippet"].as_str().unwrap().to_string();
+        assert!(snippet.contains("&lt;script&gt;alert(1)&lt;/script&gt;"), "{}", snippet);
+        assert!(!snippet.contains("<script>"), "{}", snippet);
+        assert!(!snippet.contains("<mark>fake</mark>"), "{}", snippet);
+        assert_eq!(snippet.matches("<mark>").count(), 2, "{}", snippet);
+        assert!(snippet.starts_with("<mark>Borrowing</mark>"), "{}", snippet);
+        assert!(snippet.ends_with("<mark>borrow</mark> checker"), "{}", snippet);
+
+        let empty = app.send(request("GET", "/search?q=%20", None)).await;
+        assert_eq!(empty.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,375 @@
+use crate::config::PaginationConfig;
+use crate::routes::pagination::paginated_reply;
+use crate::store::Store;
+use crate::types::account::Session;
+use crate::types::filter::{QUESTION_FILTER_PARAMS, check_cursor_sort, extract_question_filter};
+use crate::types::pagination::{PAGINATION_PARAMS, extract_pagination, reject_unknown_params};
+use crate::types::tag::{NewTagMerge, NewTagSynonym, UpdateTag, normalize_tag};
+
+use handle_errors::{Error, FieldError};
+
+use percent_encoding::percent_decode_str;
+
+use std::collections::HashMap;
+
+use warp::{Rejection, Reply, http::StatusCode};
+
+use tracing::{Level, event};
+
+/// Longest tag description accepted
+const MAX_DESCRIPTION_LENGTH: usize = 500;
+
+/// Lists the tags, most used first
+/// # Example query
+/// `/tags?limit=50&offset=0`
+pub async fn get_tags(
+    params: HashMap<String, String>,
+    config: PaginationConfig,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "querying tags");
+
+    // Tags are ordered by usage, which cursors cannot follow
+    reject_unknown_params(&params, &["limit", "offset"])?;
+    let pagination = extract_pagination(&params, &config)?;
+
+    match store.get_tags(pagination.limit, pagination.offset).await {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+pub async fn get_tag(name: String, store: Store) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "querying tag {}", name);
+
+    match store.get_tag(&resolve(name, &store).await?).await {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Lists the questions carrying a tag, with the same filters,
+/// ordering and pagination as the `/questions` route
+pub async fn get_tag_questions(
+    name: String,
+    params: HashMap<String, String>,
+    config: PaginationConfig,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "querying questions tagged {}", name);
+
+    let known: Vec<&str> = QUESTION_FILTER_PARAMS
+        .iter()
+        .filter(|param| !matches!(**param, "tag" | "tag_match"))
+        .chain(PAGINATION_PARAMS.iter())
+        .copied()
+        .collect();
+    reject_unknown_params(&params, &known)?;
+
+    let tag = store.get_tag(&resolve(name, &store).await?).await?;
+
+    let mut filter = extract_question_filter(&params)?;
+    filter.tags = vec![tag.name.clone()];
+    let pagination = extract_pagination(&params, &config)?;
+    check_cursor_sort(&filter, &pagination)?;
+
+    match store.get_questions(&filter, &pagination).await {
+        Ok(page) => Ok(paginated_reply(
+            &format!("/tags/{}/questions", tag.name),
+            &params,
+            &pagination,
+            page,
+            filter.sort.is_chronological(),
+        )),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Sets the description of a tag, moderators only
+pub async fn update_tag(
+    name: String,
+    session: Session,
+    store: Store,
+    tag: UpdateTag,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} updating tag {}", session.account_id, name);
+
+    if !session.is_moderator() {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    // An empty description removes it
+    let description = tag
+        .description
+        .map(|description| description.trim().to_string())
+        .filter(|description| !description.is_empty());
+
+    if description
+        .as_ref()
+        .is_some_and(|description| description.chars().count() > MAX_DESCRIPTION_LENGTH)
+    {
+        return Err(warp::reject::custom(Error::ValidationError(vec![FieldError::new(
+            "description",
+            &format!("must be at most {} characters long", MAX_DESCRIPTION_LENGTH),
+        )])));
+    }
+
+    match store
+        .update_tag(&resolve(name, &store).await?, UpdateTag { description })
+        .await
+    {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+pub async fn get_tag_synonyms(name: String, store: Store) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "querying synonyms of tag {}", name);
+
+    match store.get_tag_synonyms(&canonical_name(name)).await {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Makes `alias` a synonym of the tag, moderators only. Tags which are
+/// in use already cannot become synonyms, they have to be merged instead.
+pub async fn add_tag_synonym(
+    name: String,
+    session: Session,
+    store: Store,
+    synonym: NewTagSynonym,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} adding synonym to tag {}", session.account_id, name);
+
+    if !session.is_moderator() {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    let name = canonical_name(name);
+    let alias = match normalize_tag(&synonym.alias) {
+        Some(alias) if alias == name => {
+            return Err(warp::reject::custom(Error::ValidationError(vec![FieldError::new(
+                "alias",
+                "must differ from the tag",
+            )])));
+        }
+        Some(alias) => alias,
+        None => {
+            return Err(warp::reject::custom(Error::ValidationError(vec![FieldError::new(
+                "alias",
+                "may only contain letters, digits, spaces and + . - _",
+            )])));
+        }
+    };
+
+    match store.get_tag(&alias).await {
+        Ok(_) => {
+            return Err(warp::reject::custom(Error::ValidationError(vec![FieldError::new(
+                "alias",
+                &format!("{} is a tag already, merge it instead", alias),
+            )])));
+        }
+        Err(Error::NotFoundByName { .. }) => (),
+        Err(e) => return Err(warp::reject::custom(e)),
+    }
+
+    match store.add_tag_synonym(&name, &alias, &session.account_id).await {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Removes a synonym of a tag, moderators only
+pub async fn delete_tag_synonym(
+    name: String,
+    alias: String,
+    session: Session,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(
+        target: "rwd",
+        Level::INFO,
+        "account {} removing synonym {} of tag {}",
+        session.account_id,
+        alias,
+        name
+    );
+
+    if !session.is_moderator() {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    let alias = canonical_name(alias);
+    match store.delete_tag_synonym(&canonical_name(name), &alias).await {
+        Ok(_) => Ok(warp::reply::with_status(
+            format!("Synonym {} deleted", alias),
+            StatusCode::OK,
+        )),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Merges the tag into another one on every question, moderators only.
+/// The merged tag lives on as a synonym of the one it was merged into.
+pub async fn merge_tags(
+    name: String,
+    session: Session,
+    store: Store,
+    merge: NewTagMerge,
+) -> Result<impl Reply, Rejection> {
+    event!(
+        target: "rwd",
+        Level::INFO,
+        "account {} merging tag {} into {}",
+        session.account_id,
+        name,
+        merge.into
+    );
+
+    if !session.is_moderator() {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    let source = canonical_name(name);
+    let target = resolve(merge.into, &store).await?;
+
+    if source == target {
+        return Err(warp::reject::custom(Error::ValidationError(vec![FieldError::new(
+            "into",
+            "must differ from the merged tag",
+        )])));
+    }
+
+    match store.merge_tags(&source, &target, &session.account_id).await {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Lists the recorded tag merges, newest first, moderators only
+pub async fn get_tag_merges(
+    params: HashMap<String, String>,
+    config: PaginationConfig,
+    session: Session,
+    store: Store,
+) -> Result<impl Reply, Rejection> {
+    event!(target: "rwd", Level::INFO, "account {} querying tag merges", session.account_id);
+
+    if !session.is_moderator() {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    reject_unknown_params(&params, &["limit", "offset"])?;
+    let pagination = extract_pagination(&params, &config)?;
+
+    match store.get_tag_merges(pagination.limit, pagination.offset).await {
+        Ok(res) => Ok(warp::reply::json(&res)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Normalizes a tag from the path and follows it if it is a synonym
+async fn resolve(name: String, store: &Store) -> Result<String, Error> {
+    let name = canonical_name(name);
+    let resolved = store.resolve_tag_synonyms(vec![name.clone()]).await?;
+
+    Ok(resolved.into_iter().next().unwrap_or(name))
+}
+
+/// Lets `/tags/Rust` and `/tags/async%20await` find the tags `rust` and
+/// `async-await`, names which cannot be normalized are looked up as given
+fn canonical_name(name: String) -> String {
+    let decoded = percent_decode_str(&name).decode_utf8_lossy();
+    normalize_tag(&decoded).unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Policy;
+    use crate::routes::testing::{app, body, request};
+    use crate::types::account::Role;
+
+    use serde_json::json;
+    use warp::http::StatusCode;
+
+    fn question(tags: &[&str]) -> serde_json::Value {
+        json!({ "title": "A title", "content": "Some content", "tags": tags })
+    }
+
+    #[tokio::test]
+    async fn synonyms_are_replaced_by_their_tag() {
+        let app = app(Policy::Censor);
+        let ann = app.sign_up("ann@example.com", Role::User).await;
+        let moderator = app.sign_up("mod@example.com", Role::Moderator).await;
+        app.send(request("POST", "/questions", Some(&ann)).json(&question(&["rust", "go"])))
+            .await;
+
+        let synonym = |token: &str, alias: &str| {
+            request("POST", "/tags/rust/synonyms", Some(token)).json(&json!({ "alias": alias }))
+        };
+
+        let added = app.send(synonym(&ann, "Rust Lang")).await;
+        assert_eq!(added.status(), StatusCode::FORBIDDEN);
+        let added = app.send(synonym(&moderator, "Rust Lang")).await;
+        assert_eq!(added.status(), StatusCode::OK);
+        assert_eq!(body(&added)["alias"], "rust-lang");
+
+        // Tags in use have to be merged instead
+        let taken = app.send(synonym(&moderator, "go")).await;
+        assert_eq!(taken.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(body(&taken)["errors"][0]["field"], "alias");
+
+        let asked = app
+            .send(request("POST", "/questions", Some(&ann)).json(&question(&["Rust_Lang"])))
+            .await;
+        assert_eq!(asked.status(), StatusCode::OK);
+        let fetched = app.send(request("GET", "/questions/2", None)).await;
+        assert_eq!(body(&fetched)["tags"], json!(["rust"]));
+
+        let tag = app.send(request("GET", "/tags/rust-lang", None)).await;
+        assert_eq!(body(&tag)["name"], "rust");
+        assert_eq!(body(&tag)["question_count"], 2);
+    }
+
+    #[tokio::test]
+    async fn merged_tags_live_on_as_synonyms() {
+        let app = app(Policy::Censor);
+        let ann = app.sign_up("ann@example.com", Role::User).await;
+        let moderator = app.sign_up("mod@example.com", Role::Moderator).await;
+        for tags in [&["golang"][..], &["go", "golang"], &["go"]] {
+            app.send(request("POST", "/questions", Some(&ann)).json(&question(tags)))
+                .await;
+        }
+
+        let merge = |into: &str| {
+            request("POST", "/tags/golang/merge", Some(&moderator)).json(&json!({ "into": into }))
+        };
+
+        let itself = app.send(merge("golang")).await;
+        assert_eq!(itself.status(), StatusCode::BAD_REQUEST);
+        let unknown = app.send(merge("rust")).await;
+        assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+
+        let merged = app.send(merge("go")).await;
+        assert_eq!(merged.status(), StatusCode::OK);
+        assert_eq!(body(&merged)["question_ids"], json!([1, 2]));
+
+        for id in 1..=3 {
+            let fetched = app
+                .send(request("GET", &format!("/questions/{}", id), None))
+                .await;
+            assert_eq!(body(&fetched)["tags"], json!(["go"]));
+        }
+
+        let tag = app.send(request("GET", "/tags/golang", None)).await;
+        assert_eq!(body(&tag)["name"], "go");
+        assert_eq!(body(&tag)["question_count"], 3);
+
+        let merges = app
+            .send(request("GET", "/tag-merges", Some(&moderator)))
+            .await;
+        assert_eq!(body(&merges)[0]["source"], "golang");
+        assert_eq!(body(&merges)[0]["target"], "go");
+    }
+}
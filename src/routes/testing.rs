@@ -0,0 +1,115 @@
+//! Helpers for the handler tests, which run the routes on a fresh in-memory store
+
+use super::routes;
+use crate::config::{AuthConfig, PaginationConfig, Policy};
+use crate::profanity::{LocalCensor, Screen};
+use crate::store::MemoryStore;
+use crate::types::account::Role;
+
+use serde_json::{Value, json};
+use std::sync::Arc;
+use warp::http::{Response, StatusCode};
+use warp::hyper::body::Bytes;
+use warp::test::RequestBuilder;
+use warp::{Filter, Rejection, Reply};
+
+/// The routes and the store behind them, pages hold 2 items by default
+pub struct App<F> {
+    pub store: MemoryStore,
+    pub routes: F,
+}
+
+/// Censors "shit", the policy decides what happens to posts containing it
+pub fn app(policy: Policy) -> App<impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone> {
+    let store = MemoryStore::new();
+    let censor = LocalCensor::new(&["shit".to_string()], &[], '*', false).unwrap();
+    let routes = routes(
+        AuthConfig {
+            token_secret: "secret-used-by-the-handler-tests-only".to_string(),
+            token_ttl_minutes: 60,
+        },
+        PaginationConfig {
+            default_limit: 2,
+            max_limit: 5,
+        },
+        Arc::new(store.clone()),
+        Screen::new(Arc::new(censor), policy, 1),
+    );
+
+    App { store, routes }
+}
+
+pub fn request(method: &str, path: &str, token: Option<&str>) -> RequestBuilder {
+    let request = warp::test::request().method(method).path(path);
+    match token {
+        Some(token) => request.header("authorization", token),
+        None => request,
+    }
+}
+
+pub fn body(response: &Response<Bytes>) -> Value {
+    serde_json::from_slice(response.body()).unwrap()
+}
+
+/// Ids of the items of a listing
+pub fn ids(response: &Response<Bytes>) -> Vec<i64> {
+    body(response)
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["id"].as_i64().unwrap())
+        .collect()
+}
+
+impl<F, R> App<F>
+where
+    F: Filter<Extract = (R,), Error = Rejection> + Clone + 'static,
+    R: Reply + Send,
+{
+    pub async fn send(&self, request: RequestBuilder) -> Response<Bytes> {
+        request.reply(&self.routes).await
+    }
+
+    /// Registers an account and returns its bearer token
+    pub async fn sign_up(&self, email: &str, role: Role) -> String {
+        let credentials = json!({ "email": email, "password": "long enough" });
+
+        let registered = self.send(request("POST", "/registration", None).json(&credentials));
+        assert_eq!(registered.await.status(), StatusCode::OK);
+
+        // Roles are never granted through the API
+        for account in self.store.accounts.write().values_mut() {
+            if account.email == email {
+                account.role = role;
+            }
+        }
+
+        let login = self
+            .send(request("POST", "/login", None).json(&credentials))
+            .await;
+        assert_eq!(login.status(), StatusCode::OK);
+
+        format!("Bearer {}", body(&login).as_str().unwrap())
+    }
+
+    pub async fn ask(&self, token: &str, title: &str, content: &str) -> Response<Bytes> {
+        let question = json!({ "title": title, "content": content });
+        self.send(request("POST", "/questions", Some(token)).json(&question))
+            .await
+    }
+
+    /// Answers are posted as a form
+    pub async fn answer(&self, token: &str, question_id: i32, content: &str) -> Response<Bytes> {
+        let answer = format!(
+            "question_id={}&content={}",
+            question_id,
+            content.replace(' ', "+")
+        );
+        self.send(
+            request("POST", "/answers", Some(token))
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(answer),
+        )
+        .await
+    }
+}
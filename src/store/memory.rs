@@ -0,0 +1,1590 @@
+use crate::types::account::{Account, AccountId};
+use crate::types::answer::{Answer, AnswerId, NewAnswer, UpdateAnswer};
+use crate::types::comment::{Comment, CommentId, CommentTarget, NewComment};
+use crate::types::filter::{QuestionFilter, QuestionSort, TagMatch};
+use crate::types::moderation::{
+    Flag, FlagTarget, ModerationAction, ModerationActionKind, NewFlag, NewModerationAction,
+    QueueEntry,
+};
+use crate::types::pagination::{Cursor, Page, Pagination, Position};
+use crate::types::question::{
+    NewQuestion, Question, QuestionId, QuestionSummary, QuestionWithAnswers,
+};
+use crate::types::revision::{AnswerRevision, QuestionRevision};
+use crate::types::search::{SearchResult, escape_html};
+use crate::types::tag::{Tag, TagMerge, TagSynonym, UpdateTag};
+use crate::types::vote::Vote;
+
+use super::{
+    AccountRepository, AnswerRepository, CommentRepository, ModerationRepository,
+    QuestionRepository, TagRepository,
+};
+
+use handle_errors::{DatabaseError, DatabaseErrorKind, Error, Resource};
+
+use async_trait::async_trait;
+use chrono::{DateTime, SubsecRound, Utc};
+use parking_lot::RwLock;
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::Arc,
+};
+
+/// Storage backend keeping everything in memory,
+/// used for local demos and handler tests without Postgres
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStore {
+    pub questions: Arc<RwLock<BTreeMap<QuestionId, Question>>>,
+    pub answers: Arc<RwLock<BTreeMap<AnswerId, Answer>>>,
+    /// Soft deleted questions, kept apart so no lookup can see them
+    pub deleted_questions: Arc<RwLock<BTreeMap<QuestionId, Question>>>,
+    /// Soft deleted answers
+    pub deleted_answers: Arc<RwLock<BTreeMap<AnswerId, Answer>>>,
+    pub accounts: Arc<RwLock<BTreeMap<AccountId, Account>>>,
+    /// Every version of every question, oldest first
+    pub question_revisions: Arc<RwLock<BTreeMap<QuestionId, Vec<QuestionRevision>>>>,
+    /// Every version of every answer, oldest first
+    pub answer_revisions: Arc<RwLock<BTreeMap<AnswerId, Vec<AnswerRevision>>>>,
+    /// The vote of every account on every question
+    pub question_votes: Arc<RwLock<BTreeMap<(QuestionId, AccountId), Vote>>>,
+    /// The vote of every account on every answer
+    pub answer_votes: Arc<RwLock<BTreeMap<(AnswerId, AccountId), Vote>>>,
+    pub comments: Arc<RwLock<BTreeMap<CommentId, Comment>>>,
+    /// Every tag ever used with its description, like the `tags` table
+    pub tags: Arc<RwLock<BTreeMap<String, Option<String>>>>,
+    /// Aliases with the tags they stand for
+    pub tag_synonyms: Arc<RwLock<BTreeMap<String, TagSynonym>>>,
+    /// Recorded tag merges, oldest first
+    pub tag_merges: Arc<RwLock<Vec<TagMerge>>>,
+    pub flags: Arc<RwLock<BTreeMap<i32, Flag>>>,
+    /// Hidden questions, which are in `deleted_questions` as well
+    pub hidden_questions: Arc<RwLock<BTreeSet<QuestionId>>>,
+    /// Hidden answers, which are in `deleted_answers` as well
+    pub hidden_answers: Arc<RwLock<BTreeSet<AnswerId>>>,
+    /// Hidden questions their author had deleted before,
+    /// dismissing the flags leaves them deleted
+    pub hidden_deleted_questions: Arc<RwLock<BTreeSet<QuestionId>>>,
+    /// Hidden answers their author had deleted before
+    pub hidden_deleted_answers: Arc<RwLock<BTreeSet<AnswerId>>>,
+    /// Recorded moderator actions, oldest first
+    pub moderation_actions: Arc<RwLock<Vec<ModerationAction>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        MemoryStore::default()
+    }
+
+    /// Creates a store seeded with the questions of a JSON file
+    /// in the format of `questions.json`
+    pub fn from_file(path: &str) -> Self {
+        let file = std::fs::read_to_string(path).expect("can't read seed file");
+        let questions: HashMap<String, Question> =
+            serde_json::from_str(&file).expect("can't parse seed file");
+
+        // The seed file carries no timestamps
+        let created_on = now();
+
+        let store = MemoryStore::new();
+        for question in questions.into_values() {
+            let question = Question {
+                created_on,
+                updated_on: created_on,
+                ..question
+            };
+            store.record_question_revision(&question, question.account_id.clone());
+            store.register_tags(&question);
+            store.questions.write().insert(question.id.clone(), question);
+        }
+
+        store
+    }
+
+    /// Raises the flag without an account of a post the content policy holds
+    fn raise_hold_flag(
+        &self,
+        question_id: Option<QuestionId>,
+        answer_id: Option<AnswerId>,
+        flag: NewFlag,
+    ) {
+        let mut flags = self.flags.write();
+        let id = flags.keys().next_back().map_or(1, |id| id + 1);
+        flags.insert(
+            id,
+            Flag {
+                id,
+                question_id,
+                answer_id,
+                account_id: None,
+                reason: flag.reason,
+                details: flag.details,
+                created_on: now(),
+                resolved_on: None,
+            },
+        );
+    }
+
+    /// Takes back the acceptance of an answer which is deleted,
+    /// like the trigger on `answers.deleted_at`
+    fn unaccept(&self, answer: &Answer) {
+        for questions in [&self.questions, &self.deleted_questions] {
+            if let Some(question) = questions.write().get_mut(&answer.question_id)
+                && question.accepted_answer_id.as_ref() == Some(&answer.id)
+            {
+                question.accepted_answer_id = None;
+            }
+        }
+    }
+
+    /// Adds the tags of a question to the list of known tags
+    fn register_tags(&self, question: &Question) {
+        let mut tags = self.tags.write();
+        for tag in question.tags.iter().flatten() {
+            tags.entry(tag.clone()).or_default();
+        }
+    }
+
+    /// Counts the tag on every question which is not deleted
+    fn tag(&self, name: &str, description: Option<String>) -> Tag {
+        let question_count = self
+            .questions
+            .read()
+            .values()
+            .filter(|q| q.tags.iter().flatten().any(|tag| tag == name))
+            .count();
+
+        Tag {
+            name: name.to_string(),
+            description,
+            question_count: question_count as i64,
+        }
+    }
+
+    /// Appends the current state of a question to its history
+    fn record_question_revision(&self, question: &Question, account_id: Option<AccountId>) {
+        let mut revisions = self.question_revisions.write();
+        let history = revisions.entry(question.id.clone()).or_default();
+
+        history.push(QuestionRevision {
+            question_id: question.id.clone(),
+            revision: history.len() as i32 + 1,
+            title: question.title.clone(),
+            content: question.content.clone(),
+            tags: question.tags.clone(),
+            account_id,
+            created_on: question.updated_on,
+        });
+    }
+
+    /// Appends the current state of an answer to its history
+    fn record_answer_revision(&self, answer: &Answer, account_id: Option<AccountId>) {
+        let mut revisions = self.answer_revisions.write();
+        let history = revisions.entry(answer.id.clone()).or_default();
+
+        history.push(AnswerRevision {
+            answer_id: answer.id.clone(),
+            revision: history.len() as i32 + 1,
+            content: answer.content.clone(),
+            account_id,
+            created_on: answer.updated_on,
+        });
+    }
+}
+
+#[async_trait]
+impl QuestionRepository for MemoryStore {
+    async fn get_questions(
+        &self,
+        filter: &QuestionFilter,
+        pagination: &Pagination,
+    ) -> Result<Page<QuestionSummary>, Error> {
+        let answers = self.answers.read();
+        let answer_count = |id: &QuestionId| answers.values().filter(|a| &a.question_id == id).count();
+
+        let mut questions: Vec<(Position, &Question)> = Vec::new();
+        let stored = self.questions.read();
+
+        for question in stored.values() {
+            let tags = question.tags.as_deref().unwrap_or_default();
+            let tags_match = filter.tags.is_empty()
+                || match filter.tag_match {
+                    TagMatch::Any => filter.tags.iter().any(|t| tags.contains(t)),
+                    TagMatch::All => filter.tags.iter().all(|t| tags.contains(t)),
+                };
+
+            let created_on = question.created_on;
+            let in_range = filter.created_after.is_none_or(|after| created_on > after)
+                && filter.created_before.is_none_or(|before| created_on < before);
+
+            let answered = filter
+                .answered
+                .is_none_or(|answered| answered == (answer_count(&question.id) > 0));
+
+            if tags_match && in_range && answered {
+                let position = Position {
+                    created_on,
+                    id: question.id.0,
+                };
+                questions.push((position, question));
+            }
+        }
+
+        questions.sort_by(|(pa, a), (pb, b)| match filter.sort {
+            QuestionSort::Newest => pb.cmp(pa),
+            QuestionSort::Oldest => pa.cmp(pb),
+            QuestionSort::MostAnswers => answer_count(&b.id)
+                .cmp(&answer_count(&a.id))
+                .then(b.id.cmp(&a.id)),
+            QuestionSort::Score => b.score.cmp(&a.score).then(b.id.cmp(&a.id)),
+            QuestionSort::Title => a.title.cmp(&b.title).then(a.id.cmp(&b.id)),
+        });
+
+        let summaries: Vec<(Position, QuestionSummary)> = questions
+            .into_iter()
+            .map(|(position, question)| {
+                let summary = QuestionSummary {
+                    answer_count: answer_count(&question.id) as i64,
+                    question: question.clone(),
+                };
+                (position, summary)
+            })
+            .collect();
+
+        let descending = filter.sort != QuestionSort::Oldest;
+        Ok(paginate(summaries, |p| *p, descending, pagination))
+    }
+
+    async fn get_question(&self, question_id: i32) -> Result<Question, Error> {
+        match self.questions.read().get(&QuestionId(question_id)) {
+            Some(question) => Ok(question.clone()),
+            None => Err(Error::NotFound {
+                resource: Resource::Question,
+                id: question_id,
+            }),
+        }
+    }
+
+    async fn get_question_with_answers(
+        &self,
+        question_id: i32,
+    ) -> Result<QuestionWithAnswers, Error> {
+        let question = self.get_question(question_id).await?;
+
+        let mut answers: Vec<Answer> = self
+            .answers
+            .read()
+            .values()
+            .filter(|a| a.question_id == question.id)
+            .cloned()
+            .collect();
+        answers.sort_by_key(|a| {
+            let accepted = Some(&a.id) == question.accepted_answer_id.as_ref();
+            (!accepted, a.created_on, a.id.clone())
+        });
+
+        Ok(QuestionWithAnswers {
+            answer_count: answers.len() as i64,
+            answers,
+            question,
+        })
+    }
+
+    async fn add_question(
+        &self,
+        question: NewQuestion,
+        account_id: &AccountId,
+        hold: Option<NewFlag>,
+    ) -> Result<Question, Error> {
+        let mut questions = self.questions.write();
+        // Like a serial column, ids of deleted questions are not reused
+        let id = questions
+            .keys()
+            .chain(self.deleted_questions.read().keys())
+            .map(|id| id.0)
+            .max()
+            .map_or(1, |id| id + 1);
+        let created_on = now();
+
+        let question = Question {
+            id: QuestionId(id),
+            title: question.title,
+            content: question.content,
+            tags: question.tags,
+            account_id: Some(account_id.clone()),
+            created_on,
+            updated_on: created_on,
+            score: 0,
+            accepted_answer_id: None,
+        };
+        match hold {
+            Some(flag) => {
+                self.deleted_questions
+                    .write()
+                    .insert(question.id.clone(), question.clone());
+                self.hidden_questions.write().insert(question.id.clone());
+                self.raise_hold_flag(Some(question.id.clone()), None, flag);
+            }
+            None => {
+                questions.insert(question.id.clone(), question.clone());
+            }
+        }
+        self.record_question_revision(&question, question.account_id.clone());
+        self.register_tags(&question);
+
+        Ok(question)
+    }
+
+    async fn update_question(
+        &self,
+        question: NewQuestion,
+        question_id: i32,
+        account_id: &AccountId,
+        hold: Option<NewFlag>,
+    ) -> Result<Question, Error> {
+        let id = QuestionId(question_id);
+        let mut questions = self.questions.write();
+
+        let question = match questions.get_mut(&id) {
+            Some(stored) => {
+                stored.title = question.title;
+                stored.content = question.content;
+                stored.tags = question.tags;
+                stored.updated_on = now();
+                self.record_question_revision(stored, Some(account_id.clone()));
+                self.register_tags(stored);
+                stored.clone()
+            }
+            None => {
+                return Err(Error::NotFound {
+                    resource: Resource::Question,
+                    id: question_id,
+                });
+            }
+        };
+
+        if let Some(flag) = hold {
+            questions.remove(&id);
+            self.deleted_questions
+                .write()
+                .insert(id.clone(), question.clone());
+            self.hidden_questions.write().insert(id.clone());
+            self.raise_hold_flag(Some(id), None, flag);
+        }
+
+        Ok(question)
+    }
+
+    async fn delete_question(&self, question_id: i32) -> Result<bool, Error> {
+        let id = QuestionId(question_id);
+        let question = self.questions.write().remove(&id);
+
+        match question {
+            Some(question) => {
+                self.deleted_questions.write().insert(id, question);
+                Ok(true)
+            }
+            None => Err(Error::NotFound {
+                resource: Resource::Question,
+                id: question_id,
+            }),
+        }
+    }
+
+    async fn restore_question(&self, question_id: i32) -> Result<Question, Error> {
+        let id = QuestionId(question_id);
+        let question = self.deleted_questions.write().remove(&id);
+
+        match question {
+            Some(question) => {
+                self.hidden_questions.write().remove(&id);
+                self.hidden_deleted_questions.write().remove(&id);
+                self.questions.write().insert(id, question.clone());
+                Ok(question)
+            }
+            None => Err(Error::NotFound {
+                resource: Resource::Question,
+                id: question_id,
+            }),
+        }
+    }
+
+    async fn purge_question(&self, question_id: i32) -> Result<bool, Error> {
+        let id = QuestionId(question_id);
+
+        let visible = self.questions.write().remove(&id);
+        let deleted = self.deleted_questions.write().remove(&id);
+        if visible.is_none() && deleted.is_none() {
+            return Err(Error::NotFound {
+                resource: Resource::Question,
+                id: question_id,
+            });
+        }
+
+        let mut purged = Vec::new();
+        for answers in [&self.answers, &self.deleted_answers] {
+            answers.write().retain(|answer_id, answer| {
+                if answer.question_id == id {
+                    purged.push(answer_id.clone());
+                    return false;
+                }
+                true
+            });
+        }
+
+        self.answer_votes
+            .write()
+            .retain(|(answer_id, _), _| !purged.contains(answer_id));
+        self.question_votes
+            .write()
+            .retain(|(question_id, _), _| question_id != &id);
+        self.comments.write().retain(|_, comment| {
+            comment.question_id.as_ref() != Some(&id)
+                && comment.answer_id.as_ref().is_none_or(|a| !purged.contains(a))
+        });
+        self.flags.write().retain(|_, flag| {
+            flag.question_id.as_ref() != Some(&id)
+                && flag.answer_id.as_ref().is_none_or(|a| !purged.contains(a))
+        });
+        self.hidden_questions.write().remove(&id);
+        self.hidden_deleted_questions.write().remove(&id);
+        self.hidden_answers
+            .write()
+            .retain(|answer_id| !purged.contains(answer_id));
+        self.hidden_deleted_answers
+            .write()
+            .retain(|answer_id| !purged.contains(answer_id));
+
+        let mut answer_revisions = self.answer_revisions.write();
+        for answer_id in purged {
+            answer_revisions.remove(&answer_id);
+        }
+        self.question_revisions.write().remove(&id);
+
+        Ok(true)
+    }
+
+    async fn vote_question(
+        &self,
+        question_id: i32,
+        account_id: &AccountId,
+        vote: Vote,
+    ) -> Result<Question, Error> {
+        let id = QuestionId(question_id);
+
+        match self.questions.write().get_mut(&id) {
+            Some(stored) => {
+                let previous = self
+                    .question_votes
+                    .write()
+                    .insert((id, account_id.clone()), vote);
+                stored.score += i32::from(vote.value())
+                    - previous.map_or(0, |previous| i32::from(previous.value()));
+                Ok(stored.clone())
+            }
+            None => Err(Error::NotFound {
+                resource: Resource::Question,
+                id: question_id,
+            }),
+        }
+    }
+
+    async fn accept_answer(&self, question_id: i32, answer_id: i32) -> Result<Question, Error> {
+        let answer_id = AnswerId(answer_id);
+        let belongs = self
+            .answers
+            .read()
+            .get(&answer_id)
+            .is_some_and(|answer| answer.question_id == QuestionId(question_id));
+        if !belongs {
+            return Err(Error::NotFound {
+                resource: Resource::Answer,
+                id: answer_id.0,
+            });
+        }
+
+        match self.questions.write().get_mut(&QuestionId(question_id)) {
+            Some(stored) => {
+                stored.accepted_answer_id = Some(answer_id);
+                Ok(stored.clone())
+            }
+            None => Err(Error::NotFound {
+                resource: Resource::Question,
+                id: question_id,
+            }),
+        }
+    }
+
+    async fn get_question_revisions(
+        &self,
+        question_id: i32,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<QuestionRevision>, Error> {
+        let id = QuestionId(question_id);
+        let visible = self.questions.read().contains_key(&id);
+
+        match self.question_revisions.read().get(&id) {
+            Some(revisions) if visible => Ok(revisions
+                .iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .cloned()
+                .collect()),
+            _ => Err(Error::NotFound {
+                resource: Resource::Question,
+                id: question_id,
+            }),
+        }
+    }
+
+    async fn get_question_revision(
+        &self,
+        question_id: i32,
+        revision: i32,
+    ) -> Result<QuestionRevision, Error> {
+        let revisions = self.get_question_revisions(question_id, u32::MAX, 0).await?;
+
+        match revisions.into_iter().find(|r| r.revision == revision) {
+            Some(revision) => Ok(revision),
+            None => Err(Error::NotFound {
+                resource: Resource::Revision,
+                id: revision,
+            }),
+        }
+    }
+
+    async fn search_questions(
+        &self,
+        query: &str,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<SearchResult>, Error> {
+        let terms: Vec<String> = query
+            .split_whitespace()
+            .map(|t| t.trim_matches('"').to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let answers = self.answers.read();
+        let mut results: Vec<SearchResult> = self
+            .questions
+            .read()
+            .values()
+            .filter_map(|question| {
+                let answer_text: Vec<&str> = answers
+                    .values()
+                    .filter(|a| a.question_id == question.id)
+                    .map(|a| a.content.as_str())
+                    .collect();
+                let text = format!("{} {}", question.content, answer_text.join(" "));
+
+                // Weighted like the title, content and answer parts of the search vector
+                let mut rank = 0.0;
+                for term in &terms {
+                    let weight = [
+                        (question.title.as_str(), 1.0),
+                        (question.content.as_str(), 0.4),
+                        (text.as_str(), 0.2),
+                    ]
+                    .iter()
+                    .find(|(part, _)| part.to_lowercase().contains(term.as_str()))
+                    .map(|(_, weight)| *weight)?;
+                    rank += weight;
+                }
+
+                Some(SearchResult {
+                    question: question.clone(),
+                    rank,
+                    snippet: highlight(&format!("{} {}", question.title, text), &terms),
+                })
+            })
+            .collect();
+
+        results.sort_by(|a, b| {
+            b.rank
+                .total_cmp(&a.rank)
+                .then_with(|| b.question.id.cmp(&a.question.id))
+        });
+
+        Ok(results
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn is_question_owner(
+        &self,
+        question_id: i32,
+        account_id: &AccountId,
+    ) -> Result<bool, Error> {
+        let id = QuestionId(question_id);
+        let owner = match self.questions.read().get(&id) {
+            Some(question) => Some(question.account_id.clone()),
+            None => self
+                .deleted_questions
+                .read()
+                .get(&id)
+                .map(|question| question.account_id.clone()),
+        };
+
+        match owner {
+            Some(owner) => Ok(owner.as_ref() == Some(account_id)),
+            None => Err(Error::NotFound {
+                resource: Resource::Question,
+                id: question_id,
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl AnswerRepository for MemoryStore {
+    async fn get_answers(
+        &self,
+        question_id: i32,
+        pagination: &Pagination,
+    ) -> Result<Page<Answer>, Error> {
+        // Tell an empty listing apart from a missing question
+        let accepted = self.get_question(question_id).await?.accepted_answer_id;
+        // The accepted answer is pinned to the top
+        let rank = |p: &Position| (Some(AnswerId(p.id)) != accepted, *p);
+
+        let answers = self.answers.read();
+        let mut listing: Vec<(Position, Answer)> = answers
+            .values()
+            .filter(|a| a.question_id == QuestionId(question_id))
+            .map(|a| {
+                let position = Position {
+                    created_on: a.created_on,
+                    id: a.id.0,
+                };
+                (position, a.clone())
+            })
+            .collect();
+        listing.sort_by_key(|(position, _)| rank(position));
+
+        Ok(paginate(listing, rank, false, pagination))
+    }
+
+    async fn get_answer(&self, answer_id: i32) -> Result<Answer, Error> {
+        let answer = self.answers.read().get(&AnswerId(answer_id)).cloned();
+
+        // Answers of deleted questions are hidden along with them
+        match answer {
+            Some(answer) if self.questions.read().contains_key(&answer.question_id) => Ok(answer),
+            _ => Err(Error::NotFound {
+                resource: Resource::Answer,
+                id: answer_id,
+            }),
+        }
+    }
+
+    async fn add_answer(
+        &self,
+        answer: NewAnswer,
+        account_id: &AccountId,
+        hold: Option<NewFlag>,
+    ) -> Result<Answer, Error> {
+        if self.deleted_questions.read().contains_key(&answer.question_id) {
+            return Err(Error::NotFound {
+                resource: Resource::Question,
+                id: answer.question_id.0,
+            });
+        }
+
+        // Mirror the foreign key on `answers.question_id`
+        if !self.questions.read().contains_key(&answer.question_id) {
+            return Err(Error::DatabaseQueryError(DatabaseError::constraint(
+                DatabaseErrorKind::ForeignKeyViolation,
+                "answers_question_id_fkey",
+            )));
+        }
+
+        let mut answers = self.answers.write();
+        let id = answers
+            .keys()
+            .chain(self.deleted_answers.read().keys())
+            .map(|id| id.0)
+            .max()
+            .map_or(1, |id| id + 1);
+        let created_on = now();
+
+        let answer = Answer {
+            id: AnswerId(id),
+            content: answer.content,
+            question_id: answer.question_id,
+            account_id: Some(account_id.clone()),
+            created_on,
+            updated_on: created_on,
+            score: 0,
+        };
+        match hold {
+            Some(flag) => {
+                self.deleted_answers
+                    .write()
+                    .insert(answer.id.clone(), answer.clone());
+                self.hidden_answers.write().insert(answer.id.clone());
+                self.raise_hold_flag(None, Some(answer.id.clone()), flag);
+            }
+            None => {
+                answers.insert(answer.id.clone(), answer.clone());
+            }
+        }
+        self.record_answer_revision(&answer, answer.account_id.clone());
+
+        Ok(answer)
+    }
+
+    async fn update_answer(
+        &self,
+        answer: UpdateAnswer,
+        answer_id: i32,
+        account_id: &AccountId,
+        hold: Option<NewFlag>,
+    ) -> Result<Answer, Error> {
+        let id = AnswerId(answer_id);
+        let mut answers = self.answers.write();
+
+        let answer = match answers.get_mut(&id) {
+            Some(stored) => {
+                stored.content = answer.content;
+                stored.updated_on = now();
+                self.record_answer_revision(stored, Some(account_id.clone()));
+                stored.clone()
+            }
+            None => {
+                return Err(Error::NotFound {
+                    resource: Resource::Answer,
+                    id: answer_id,
+                });
+            }
+        };
+
+        if let Some(flag) = hold {
+            answers.remove(&id);
+            drop(answers);
+            self.unaccept(&answer);
+            self.deleted_answers
+                .write()
+                .insert(id.clone(), answer.clone());
+            self.hidden_answers.write().insert(id.clone());
+            self.raise_hold_flag(None, Some(id), flag);
+        }
+
+        Ok(answer)
+    }
+
+    async fn delete_answer(&self, answer_id: i32) -> Result<bool, Error> {
+        let id = AnswerId(answer_id);
+        let answer = self.answers.write().remove(&id);
+
+        match answer {
+            Some(answer) => {
+                self.unaccept(&answer);
+                self.deleted_answers.write().insert(id, answer);
+                Ok(true)
+            }
+            None => Err(Error::NotFound {
+                resource: Resource::Answer,
+                id: answer_id,
+            }),
+        }
+    }
+
+    async fn restore_answer(&self, answer_id: i32) -> Result<Answer, Error> {
+        let id = AnswerId(answer_id);
+        let answer = self.deleted_answers.write().remove(&id);
+
+        match answer {
+            Some(answer) => {
+                self.hidden_answers.write().remove(&id);
+                self.hidden_deleted_answers.write().remove(&id);
+                self.answers.write().insert(id, answer.clone());
+                Ok(answer)
+            }
+            None => Err(Error::NotFound {
+                resource: Resource::Answer,
+                id: answer_id,
+            }),
+        }
+    }
+
+    async fn vote_answer(
+        &self,
+        answer_id: i32,
+        account_id: &AccountId,
+        vote: Vote,
+    ) -> Result<Answer, Error> {
+        // Answers of deleted questions cannot be voted on
+        self.get_answer(answer_id).await?;
+        let id = AnswerId(answer_id);
+
+        match self.answers.write().get_mut(&id) {
+            Some(stored) => {
+                let previous = self
+                    .answer_votes
+                    .write()
+                    .insert((id, account_id.clone()), vote);
+                stored.score += i32::from(vote.value())
+                    - previous.map_or(0, |previous| i32::from(previous.value()));
+                Ok(stored.clone())
+            }
+            None => Err(Error::NotFound {
+                resource: Resource::Answer,
+                id: answer_id,
+            }),
+        }
+    }
+
+    async fn get_answer_revisions(
+        &self,
+        answer_id: i32,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<AnswerRevision>, Error> {
+        let id = AnswerId(answer_id);
+        let visible = self.answers.read().contains_key(&id);
+
+        match self.answer_revisions.read().get(&id) {
+            Some(revisions) if visible => Ok(revisions
+                .iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .cloned()
+                .collect()),
+            _ => Err(Error::NotFound {
+                resource: Resource::Answer,
+                id: answer_id,
+            }),
+        }
+    }
+
+    async fn get_answer_revision(
+        &self,
+        answer_id: i32,
+        revision: i32,
+    ) -> Result<AnswerRevision, Error> {
+        let revisions = self.get_answer_revisions(answer_id, u32::MAX, 0).await?;
+
+        match revisions.into_iter().find(|r| r.revision == revision) {
+            Some(revision) => Ok(revision),
+            None => Err(Error::NotFound {
+                resource: Resource::Revision,
+                id: revision,
+            }),
+        }
+    }
+
+    async fn is_answer_owner(&self, answer_id: i32, account_id: &AccountId) -> Result<bool, Error> {
+        let id = AnswerId(answer_id);
+        let owner = match self.answers.read().get(&id) {
+            Some(answer) => Some(answer.account_id.clone()),
+            None => self
+                .deleted_answers
+                .read()
+                .get(&id)
+                .map(|answer| answer.account_id.clone()),
+        };
+
+        match owner {
+            Some(owner) => Ok(owner.as_ref() == Some(account_id)),
+            None => Err(Error::NotFound {
+                resource: Resource::Answer,
+                id: answer_id,
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl CommentRepository for MemoryStore {
+    async fn get_comments(&self, target: &CommentTarget) -> Result<Vec<Comment>, Error> {
+        // Tell an empty listing apart from a missing or deleted target
+        match target {
+            CommentTarget::Question(id) => {
+                self.get_question(id.0).await?;
+            }
+            CommentTarget::Answer(id) => {
+                self.get_answer(id.0).await?;
+            }
+        }
+
+        let mut comments: Vec<Comment> = self
+            .comments
+            .read()
+            .values()
+            .filter(|comment| match target {
+                CommentTarget::Question(id) => comment.question_id.as_ref() == Some(id),
+                CommentTarget::Answer(id) => comment.answer_id.as_ref() == Some(id),
+            })
+            .cloned()
+            .collect();
+        comments.sort_by_key(|c| (c.created_on, c.id.clone()));
+
+        Ok(comments)
+    }
+
+    async fn add_comment(
+        &self,
+        target: &CommentTarget,
+        comment: NewComment,
+        account_id: &AccountId,
+    ) -> Result<Comment, Error> {
+        // Deleted questions and answers take no new comments
+        let (question_id, answer_id) = match target {
+            CommentTarget::Question(id) => (Some(self.get_question(id.0).await?.id), None),
+            CommentTarget::Answer(id) => (None, Some(self.get_answer(id.0).await?.id)),
+        };
+
+        let mut comments = self.comments.write();
+        let id = comments.keys().next_back().map_or(1, |id| id.0 + 1);
+
+        let comment = Comment {
+            id: CommentId(id),
+            content: comment.content,
+            question_id,
+            answer_id,
+            account_id: account_id.clone(),
+            created_on: now(),
+        };
+        comments.insert(comment.id.clone(), comment.clone());
+
+        Ok(comment)
+    }
+
+    async fn delete_comment(&self, comment_id: i32) -> Result<bool, Error> {
+        match self.comments.write().remove(&CommentId(comment_id)) {
+            Some(_) => Ok(true),
+            None => Err(Error::NotFound {
+                resource: Resource::Comment,
+                id: comment_id,
+            }),
+        }
+    }
+
+    async fn is_comment_owner(
+        &self,
+        comment_id: i32,
+        account_id: &AccountId,
+    ) -> Result<bool, Error> {
+        match self.comments.read().get(&CommentId(comment_id)) {
+            Some(comment) => Ok(&comment.account_id == account_id),
+            None => Err(Error::NotFound {
+                resource: Resource::Comment,
+                id: comment_id,
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl TagRepository for MemoryStore {
+    async fn get_tags(&self, limit: u32, offset: u32) -> Result<Vec<Tag>, Error> {
+        let known = self.tags.read().clone();
+
+        let mut tags: Vec<Tag> = known
+            .into_iter()
+            .map(|(name, description)| self.tag(&name, description))
+            .collect();
+        tags.sort_by(|a, b| {
+            b.question_count
+                .cmp(&a.question_count)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        Ok(tags
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn get_tag(&self, name: &str) -> Result<Tag, Error> {
+        let description = self.tags.read().get(name).cloned();
+
+        match description {
+            Some(description) => Ok(self.tag(name, description)),
+            None => Err(Error::NotFoundByName {
+                resource: Resource::Tag,
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    async fn update_tag(&self, name: &str, tag: UpdateTag) -> Result<Tag, Error> {
+        match self.tags.write().get_mut(name) {
+            Some(description) => *description = tag.description,
+            None => {
+                return Err(Error::NotFoundByName {
+                    resource: Resource::Tag,
+                    name: name.to_string(),
+                });
+            }
+        }
+
+        self.get_tag(name).await
+    }
+
+    async fn resolve_tag_synonyms(&self, tags: Vec<String>) -> Result<Vec<String>, Error> {
+        let synonyms = self.tag_synonyms.read();
+        let mut resolved: Vec<String> = Vec::with_capacity(tags.len());
+
+        for tag in tags {
+            let tag = match synonyms.get(&tag) {
+                Some(synonym) => synonym.tag.clone(),
+                None => tag,
+            };
+            if !resolved.contains(&tag) {
+                resolved.push(tag);
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    async fn get_tag_synonyms(&self, name: &str) -> Result<Vec<TagSynonym>, Error> {
+        // Tell an empty listing apart from a missing tag
+        self.get_tag(name).await?;
+
+        Ok(self
+            .tag_synonyms
+            .read()
+            .values()
+            .filter(|synonym| synonym.tag == name)
+            .cloned()
+            .collect())
+    }
+
+    async fn add_tag_synonym(
+        &self,
+        name: &str,
+        alias: &str,
+        account_id: &AccountId,
+    ) -> Result<TagSynonym, Error> {
+        if !self.tags.read().contains_key(name) {
+            return Err(Error::NotFoundByName {
+                resource: Resource::Tag,
+                name: name.to_string(),
+            });
+        }
+
+        let mut synonyms = self.tag_synonyms.write();
+
+        // Mirror the primary key on `tag_synonyms.alias`
+        if synonyms.contains_key(alias) {
+            return Err(Error::DatabaseQueryError(DatabaseError::constraint(
+                DatabaseErrorKind::UniqueViolation,
+                "tag_synonyms_pkey",
+            )));
+        }
+
+        let synonym = TagSynonym {
+            alias: alias.to_string(),
+            tag: name.to_string(),
+            account_id: Some(account_id.clone()),
+            created_on: now(),
+        };
+        synonyms.insert(synonym.alias.clone(), synonym.clone());
+
+        Ok(synonym)
+    }
+
+    async fn delete_tag_synonym(&self, name: &str, alias: &str) -> Result<bool, Error> {
+        let mut synonyms = self.tag_synonyms.write();
+
+        match synonyms.get(alias) {
+            Some(synonym) if synonym.tag == name => {
+                synonyms.remove(alias);
+                Ok(true)
+            }
+            _ => Err(Error::NotFoundByName {
+                resource: Resource::TagSynonym,
+                name: alias.to_string(),
+            }),
+        }
+    }
+
+    async fn merge_tags(
+        &self,
+        source: &str,
+        target: &str,
+        account_id: &AccountId,
+    ) -> Result<TagMerge, Error> {
+        for name in [source, target] {
+            if !self.tags.read().contains_key(name) {
+                return Err(Error::NotFoundByName {
+                    resource: Resource::Tag,
+                    name: name.to_string(),
+                });
+            }
+        }
+
+        // Questions carrying both tags keep only the target
+        let mut question_ids = Vec::new();
+        for questions in [&self.questions, &self.deleted_questions] {
+            for question in questions.write().values_mut() {
+                let Some(tags) = question.tags.as_mut() else {
+                    continue;
+                };
+                if !tags.iter().any(|tag| tag == source) {
+                    continue;
+                }
+
+                match tags.iter().any(|tag| tag == target) {
+                    true => tags.retain(|tag| tag != source),
+                    false => {
+                        for tag in tags.iter_mut().filter(|tag| *tag == source) {
+                            *tag = target.to_string();
+                        }
+                    }
+                }
+                question_ids.push(question.id.clone());
+            }
+        }
+        question_ids.sort();
+
+        let created_on = now();
+
+        // Aliases of the source move over, the source itself becomes one of them
+        {
+            let mut synonyms = self.tag_synonyms.write();
+            for synonym in synonyms.values_mut().filter(|synonym| synonym.tag == source) {
+                synonym.tag = target.to_string();
+            }
+            synonyms.insert(
+                source.to_string(),
+                TagSynonym {
+                    alias: source.to_string(),
+                    tag: target.to_string(),
+                    account_id: Some(account_id.clone()),
+                    created_on,
+                },
+            );
+        }
+        self.tags.write().remove(source);
+
+        let mut merges = self.tag_merges.write();
+        let merge = TagMerge {
+            id: merges.len() as i32 + 1,
+            source: source.to_string(),
+            target: target.to_string(),
+            account_id: Some(account_id.clone()),
+            question_ids,
+            created_on,
+        };
+        merges.push(merge.clone());
+
+        Ok(merge)
+    }
+
+    async fn get_tag_merges(&self, limit: u32, offset: u32) -> Result<Vec<TagMerge>, Error> {
+        Ok(self
+            .tag_merges
+            .read()
+            .iter()
+            .rev()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl ModerationRepository for MemoryStore {
+    async fn add_flag(
+        &self,
+        target: &FlagTarget,
+        flag: NewFlag,
+        account_id: &AccountId,
+    ) -> Result<Flag, Error> {
+        // Deleted and hidden posts are out of sight, nobody can flag them
+        let (question_id, answer_id) = match target {
+            FlagTarget::Question(id) => (Some(self.get_question(id.0).await?.id), None),
+            FlagTarget::Answer(id) => (None, Some(self.get_answer(id.0).await?.id)),
+        };
+
+        let mut flags = self.flags.write();
+
+        // Mirrors the unique indexes on open flags
+        if flags.values().any(|f| {
+            f.resolved_on.is_none()
+                && f.account_id.as_ref() == Some(account_id)
+                && f.question_id == question_id
+                && f.answer_id == answer_id
+        }) {
+            return Err(Error::DatabaseQueryError(DatabaseError::constraint(
+                DatabaseErrorKind::UniqueViolation,
+                "account_id",
+            )));
+        }
+
+        let id = flags.keys().next_back().map_or(1, |id| id + 1);
+        let flag = Flag {
+            id,
+            question_id,
+            answer_id,
+            account_id: Some(account_id.clone()),
+            reason: flag.reason,
+            details: flag.details,
+            created_on: now(),
+            resolved_on: None,
+        };
+        flags.insert(id, flag.clone());
+
+        Ok(flag)
+    }
+
+    async fn get_moderation_queue(
+        &self,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<QueueEntry>, Error> {
+        let open = self
+            .flags
+            .read()
+            .values()
+            .filter(|flag| flag.resolved_on.is_none())
+            .cloned()
+            .collect::<Vec<Flag>>();
+
+        let mut grouped: BTreeMap<(Option<QuestionId>, Option<AnswerId>), Vec<Flag>> =
+            BTreeMap::new();
+        for flag in open {
+            grouped
+                .entry((flag.question_id.clone(), flag.answer_id.clone()))
+                .or_default()
+                .push(flag);
+        }
+
+        let mut entries = Vec::new();
+        for ((question_id, answer_id), flags) in grouped {
+            let (title, content, hidden) = match (&question_id, &answer_id) {
+                (Some(id), _) => {
+                    let question = match self.questions.read().get(id) {
+                        Some(question) => question.clone(),
+                        None => match self.deleted_questions.read().get(id) {
+                            Some(question) => question.clone(),
+                            None => continue,
+                        },
+                    };
+                    let hidden = self.hidden_questions.read().contains(id);
+                    (Some(question.title), question.content, hidden)
+                }
+                (None, Some(id)) => {
+                    let answer = match self.answers.read().get(id) {
+                        Some(answer) => answer.clone(),
+                        None => match self.deleted_answers.read().get(id) {
+                            Some(answer) => answer.clone(),
+                            None => continue,
+                        },
+                    };
+                    let hidden = self.hidden_answers.read().contains(id);
+                    (None, answer.content, hidden)
+                }
+                (None, None) => continue,
+            };
+
+            let mut reasons = flags.iter().map(|flag| flag.reason).collect::<Vec<_>>();
+            reasons.sort();
+            reasons.dedup();
+
+            entries.push(QueueEntry {
+                question_id,
+                answer_id,
+                title,
+                content,
+                hidden,
+                flag_count: flags.len() as i64,
+                reasons,
+                first_flagged_on: flags.iter().map(|f| f.created_on).min().unwrap_or_default(),
+                last_flagged_on: flags.iter().map(|f| f.created_on).max().unwrap_or_default(),
+            });
+        }
+
+        // Answers come after questions with as many flags since as long, as in Postgres
+        entries.sort_by_key(|e| {
+            (
+                Reverse(e.flag_count),
+                e.first_flagged_on,
+                e.question_id.is_none(),
+                e.question_id.clone(),
+                e.answer_id.clone(),
+            )
+        });
+
+        Ok(entries
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn moderate(
+        &self,
+        target: &FlagTarget,
+        action: NewModerationAction,
+        account_id: &AccountId,
+    ) -> Result<ModerationAction, Error> {
+        // Dismissing only brings back hidden posts and leaves
+        // the ones their author deleted before they were hidden deleted
+        let (question_id, answer_id) = match target {
+            FlagTarget::Question(id) => {
+                let visible = self.questions.read().contains_key(id);
+                if !visible && !self.deleted_questions.read().contains_key(id) {
+                    return Err(Error::NotFound {
+                        resource: Resource::Question,
+                        id: id.0,
+                    });
+                }
+
+                let hidden = self.hidden_questions.read().contains(id);
+                let deleted_before = self.hidden_deleted_questions.read().contains(id);
+                match action.action {
+                    ModerationActionKind::Dismiss if hidden && deleted_before => {
+                        self.hidden_questions.write().remove(id);
+                        self.hidden_deleted_questions.write().remove(id);
+                    }
+                    ModerationActionKind::Dismiss if hidden => {
+                        self.restore_question(id.0).await?;
+                    }
+                    ModerationActionKind::Dismiss => (),
+                    ModerationActionKind::Hide if !visible && !hidden => {
+                        self.hidden_questions.write().insert(id.clone());
+                        self.hidden_deleted_questions.write().insert(id.clone());
+                    }
+                    ModerationActionKind::Hide => {
+                        if visible {
+                            self.delete_question(id.0).await?;
+                        }
+                        self.hidden_questions.write().insert(id.clone());
+                    }
+                    ModerationActionKind::Delete => {
+                        if visible {
+                            self.delete_question(id.0).await?;
+                        }
+                        self.hidden_questions.write().remove(id);
+                        self.hidden_deleted_questions.write().remove(id);
+                    }
+                }
+                (Some(id.clone()), None)
+            }
+            FlagTarget::Answer(id) => {
+                let visible = self.answers.read().contains_key(id);
+                if !visible && !self.deleted_answers.read().contains_key(id) {
+                    return Err(Error::NotFound {
+                        resource: Resource::Answer,
+                        id: id.0,
+                    });
+                }
+
+                let hidden = self.hidden_answers.read().contains(id);
+                let deleted_before = self.hidden_deleted_answers.read().contains(id);
+                match action.action {
+                    ModerationActionKind::Dismiss if hidden && deleted_before => {
+                        self.hidden_answers.write().remove(id);
+                        self.hidden_deleted_answers.write().remove(id);
+                    }
+                    ModerationActionKind::Dismiss if hidden => {
+                        self.restore_answer(id.0).await?;
+                    }
+                    ModerationActionKind::Dismiss => (),
+                    ModerationActionKind::Hide if !visible && !hidden => {
+                        self.hidden_answers.write().insert(id.clone());
+                        self.hidden_deleted_answers.write().insert(id.clone());
+                    }
+                    ModerationActionKind::Hide => {
+                        if visible {
+                            self.delete_answer(id.0).await?;
+                        }
+                        self.hidden_answers.write().insert(id.clone());
+                    }
+                    ModerationActionKind::Delete => {
+                        if visible {
+                            self.delete_answer(id.0).await?;
+                        }
+                        self.hidden_answers.write().remove(id);
+                        self.hidden_deleted_answers.write().remove(id);
+                    }
+                }
+                (None, Some(id.clone()))
+            }
+        };
+
+        let created_on = now();
+        let mut flag_ids = Vec::new();
+        for flag in self.flags.write().values_mut() {
+            if flag.resolved_on.is_none()
+                && flag.question_id == question_id
+                && flag.answer_id == answer_id
+            {
+                flag.resolved_on = Some(created_on);
+                flag_ids.push(flag.id);
+            }
+        }
+
+        let mut actions = self.moderation_actions.write();
+        let recorded = ModerationAction {
+            id: actions.len() as i32 + 1,
+            question_id,
+            answer_id,
+            account_id: Some(account_id.clone()),
+            action: action.action,
+            flag_ids,
+            note: action.note,
+            created_on,
+        };
+        actions.push(recorded.clone());
+
+        Ok(recorded)
+    }
+
+    async fn get_moderation_log(
+        &self,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<ModerationAction>, Error> {
+        Ok(self
+            .moderation_actions
+            .read()
+            .iter()
+            .rev()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn is_hidden(&self, target: &FlagTarget) -> Result<bool, Error> {
+        let exists = match target {
+            FlagTarget::Question(id) => {
+                self.questions.read().contains_key(id)
+                    || self.deleted_questions.read().contains_key(id)
+            }
+            FlagTarget::Answer(id) => {
+                self.answers.read().contains_key(id) || self.deleted_answers.read().contains_key(id)
+            }
+        };
+
+        match target {
+            FlagTarget::Question(id) if exists => Ok(self.hidden_questions.read().contains(id)),
+            FlagTarget::Answer(id) if exists => Ok(self.hidden_answers.read().contains(id)),
+            FlagTarget::Question(id) => Err(Error::NotFound {
+                resource: Resource::Question,
+                id: id.0,
+            }),
+            FlagTarget::Answer(id) => Err(Error::NotFound {
+                resource: Resource::Answer,
+                id: id.0,
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl AccountRepository for MemoryStore {
+    async fn add_account(&self, account: Account) -> Result<Account, Error> {
+        let mut accounts = self.accounts.write();
+
+        // Mirror the unique constraint on `users.email`
+        if accounts.values().any(|a| a.email == account.email) {
+            return Err(Error::DatabaseQueryError(DatabaseError::constraint(
+                DatabaseErrorKind::UniqueViolation,
+                "users_email_key",
+            )));
+        }
+
+        let id = accounts
+            .keys()
+            .next_back()
+            .map_or(1, |id| id.0 + 1);
+
+        let account = Account {
+            id: Some(AccountId(id)),
+            email: account.email,
+            password: account.password,
+            role: account.role,
+        };
+        accounts.insert(AccountId(id), account.clone());
+
+        Ok(account)
+    }
+
+    async fn get_account(&self, email: &str) -> Result<Option<Account>, Error> {
+        Ok(self
+            .accounts
+            .read()
+            .values()
+            .find(|a| a.email == email)
+            .cloned())
+    }
+}
+
+/// The current time at the microsecond precision of Postgres timestamps,
+/// so positions survive the round trip through a cursor
+fn now() -> DateTime<Utc> {
+    Utc::now().trunc_subsecs(6)
+}
+
+/// Cuts a page out of a complete listing, the same way
+/// the Postgres queries walk it from a cursor or an offset,
+/// the listing has to be sorted by `rank`
+fn paginate<T, R: Ord>(
+    mut listing: Vec<(Position, T)>,
+    rank: impl Fn(&Position) -> R,
+    descending: bool,
+    pagination: &Pagination,
+) -> Page<T> {
+    let total = listing.len() as i64;
+
+    if let Some(Cursor::Before(_)) = pagination.cursor {
+        listing.reverse();
+    }
+
+    let rows = listing
+        .into_iter()
+        .filter(|(position, _)| match (pagination.cursor, descending) {
+            (None, _) => true,
+            (Some(Cursor::After(p)), false) | (Some(Cursor::Before(p)), true) => {
+                rank(position) > rank(&p)
+            }
+            (Some(Cursor::After(p)), true) | (Some(Cursor::Before(p)), false) => {
+                rank(position) < rank(&p)
+            }
+        })
+        .skip(pagination.offset as usize)
+        .take(pagination.limit as usize + 1)
+        .collect();
+
+    Page::from_rows(rows, total, pagination)
+}
+
+/// Cuts a window of words around the first match, escapes
+/// them for HTML and wraps every matching word in `<mark>` tags
+fn highlight(text: &str, terms: &[String]) -> String {
+    let is_match = |word: &str| {
+        let word = word.to_lowercase();
+        terms.iter().any(|term| word.contains(term.as_str()))
+    };
+
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let first = words.iter().position(|w| is_match(w)).unwrap_or(0);
+
+    words
+        .iter()
+        .skip(first.saturating_sub(10))
+        .take(30)
+        .map(|word| match is_match(word) {
+            true => format!("<mark>{}</mark>", escape_html(word)),
+            false => escape_html(word),
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
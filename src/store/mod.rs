@@ -0,0 +1,312 @@
+mod memory;
+mod postgres;
+
+pub use memory::MemoryStore;
+pub use postgres::PostgresStore;
+
+use crate::types::account::{Account, AccountId};
+use crate::types::answer::{Answer, NewAnswer, UpdateAnswer};
+use crate::types::comment::{Comment, CommentTarget, NewComment};
+use crate::types::filter::QuestionFilter;
+use crate::types::moderation::{
+    Flag, FlagTarget, ModerationAction, NewFlag, NewModerationAction, QueueEntry,
+};
+use crate::types::pagination::{Page, Pagination};
+use crate::types::question::{NewQuestion, Question, QuestionSummary, QuestionWithAnswers};
+use crate::types::revision::{AnswerRevision, QuestionRevision};
+use crate::types::search::SearchResult;
+use crate::types::tag::{Tag, TagMerge, TagSynonym, UpdateTag};
+use crate::types::vote::Vote;
+
+use handle_errors::Error;
+
+use async_trait::async_trait;
+
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// Storage operations on questions
+#[async_trait]
+pub trait QuestionRepository: Send + Sync {
+    /// Cursors are only meaningful for the chronological sort orders
+    async fn get_questions(
+        &self,
+        filter: &QuestionFilter,
+        pagination: &Pagination,
+    ) -> Result<Page<QuestionSummary>, Error>;
+
+    async fn get_question(&self, question_id: i32) -> Result<Question, Error>;
+
+    async fn get_question_with_answers(
+        &self,
+        question_id: i32,
+    ) -> Result<QuestionWithAnswers, Error>;
+
+    /// With `hold` the question is stored hidden until a moderator reviews it
+    /// and the flag is raised without an account, all or nothing
+    async fn add_question(
+        &self,
+        question: NewQuestion,
+        account_id: &AccountId,
+        hold: Option<NewFlag>,
+    ) -> Result<Question, Error>;
+
+    /// Stores the new version and records it as a revision by `account_id`,
+    /// hiding the question and raising the flag along with it like `add_question`
+    async fn update_question(
+        &self,
+        question: NewQuestion,
+        question_id: i32,
+        account_id: &AccountId,
+        hold: Option<NewFlag>,
+    ) -> Result<Question, Error>;
+
+    /// Hides the question until it is restored
+    async fn delete_question(&self, question_id: i32) -> Result<bool, Error>;
+
+    /// Brings back a deleted or hidden question, fails with `NotFound` if it is not deleted
+    async fn restore_question(&self, question_id: i32) -> Result<Question, Error>;
+
+    /// Removes a question, deleted or not, with its answers and history for good
+    async fn purge_question(&self, question_id: i32) -> Result<bool, Error>;
+
+    /// Records the vote of `account_id`, replacing an earlier one,
+    /// and returns the question with its new score
+    async fn vote_question(
+        &self,
+        question_id: i32,
+        account_id: &AccountId,
+        vote: Vote,
+    ) -> Result<Question, Error>;
+
+    /// Marks an answer of the question as accepted, replacing an earlier one
+    async fn accept_answer(&self, question_id: i32, answer_id: i32) -> Result<Question, Error>;
+
+    /// Versions of a question, oldest first
+    async fn get_question_revisions(
+        &self,
+        question_id: i32,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<QuestionRevision>, Error>;
+
+    async fn get_question_revision(
+        &self,
+        question_id: i32,
+        revision: i32,
+    ) -> Result<QuestionRevision, Error>;
+
+    /// Full-text search over titles, contents and answers, best matches first
+    async fn search_questions(
+        &self,
+        query: &str,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<SearchResult>, Error>;
+
+    /// Fails with `NotFound` if the question does not exist, deleted ones included
+    async fn is_question_owner(
+        &self,
+        question_id: i32,
+        account_id: &AccountId,
+    ) -> Result<bool, Error>;
+}
+
+/// Storage operations on answers
+#[async_trait]
+pub trait AnswerRepository: Send + Sync {
+    /// Answers of a question, the accepted one first and the rest oldest first
+    async fn get_answers(
+        &self,
+        question_id: i32,
+        pagination: &Pagination,
+    ) -> Result<Page<Answer>, Error>;
+
+    async fn get_answer(&self, answer_id: i32) -> Result<Answer, Error>;
+
+    /// With `hold` the answer is stored hidden until a moderator reviews it
+    /// and the flag is raised without an account, all or nothing
+    async fn add_answer(
+        &self,
+        answer: NewAnswer,
+        account_id: &AccountId,
+        hold: Option<NewFlag>,
+    ) -> Result<Answer, Error>;
+
+    /// Stores the new version and records it as a revision by `account_id`,
+    /// hiding the answer and raising the flag along with it like `add_answer`
+    async fn update_answer(
+        &self,
+        answer: UpdateAnswer,
+        answer_id: i32,
+        account_id: &AccountId,
+        hold: Option<NewFlag>,
+    ) -> Result<Answer, Error>;
+
+    /// Hides the answer until it is restored
+    async fn delete_answer(&self, answer_id: i32) -> Result<bool, Error>;
+
+    /// Brings back a deleted or hidden answer, fails with `NotFound` if it is not deleted
+    async fn restore_answer(&self, answer_id: i32) -> Result<Answer, Error>;
+
+    /// Records the vote of `account_id`, replacing an earlier one,
+    /// and returns the answer with its new score
+    async fn vote_answer(
+        &self,
+        answer_id: i32,
+        account_id: &AccountId,
+        vote: Vote,
+    ) -> Result<Answer, Error>;
+
+    /// Versions of an answer, oldest first
+    async fn get_answer_revisions(
+        &self,
+        answer_id: i32,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<AnswerRevision>, Error>;
+
+    async fn get_answer_revision(
+        &self,
+        answer_id: i32,
+        revision: i32,
+    ) -> Result<AnswerRevision, Error>;
+
+    /// Fails with `NotFound` if the answer does not exist, deleted ones included
+    async fn is_answer_owner(&self, answer_id: i32, account_id: &AccountId) -> Result<bool, Error>;
+}
+
+/// Storage operations on comments
+#[async_trait]
+pub trait CommentRepository: Send + Sync {
+    /// Comments under a question or an answer, oldest first,
+    /// fails with `NotFound` if the target is missing or deleted
+    async fn get_comments(&self, target: &CommentTarget) -> Result<Vec<Comment>, Error>;
+
+    async fn add_comment(
+        &self,
+        target: &CommentTarget,
+        comment: NewComment,
+        account_id: &AccountId,
+    ) -> Result<Comment, Error>;
+
+    async fn delete_comment(&self, comment_id: i32) -> Result<bool, Error>;
+
+    /// Fails with `NotFound` if the comment does not exist
+    async fn is_comment_owner(
+        &self,
+        comment_id: i32,
+        account_id: &AccountId,
+    ) -> Result<bool, Error>;
+}
+
+/// Storage operations on tags, which are created along with the questions using them
+#[async_trait]
+pub trait TagRepository: Send + Sync {
+    /// Tags ordered by the number of questions carrying them, most used first
+    async fn get_tags(&self, limit: u32, offset: u32) -> Result<Vec<Tag>, Error>;
+
+    /// Fails with `NotFoundByName` if no question ever used the tag
+    async fn get_tag(&self, name: &str) -> Result<Tag, Error>;
+
+    async fn update_tag(&self, name: &str, tag: UpdateTag) -> Result<Tag, Error>;
+
+    /// Replaces every alias with its tag, dropping the duplicates this creates
+    async fn resolve_tag_synonyms(&self, tags: Vec<String>) -> Result<Vec<String>, Error>;
+
+    /// Aliases of a tag in alphabetical order
+    async fn get_tag_synonyms(&self, name: &str) -> Result<Vec<TagSynonym>, Error>;
+
+    /// Fails with a unique violation if the alias belongs to another tag already
+    async fn add_tag_synonym(
+        &self,
+        name: &str,
+        alias: &str,
+        account_id: &AccountId,
+    ) -> Result<TagSynonym, Error>;
+
+    async fn delete_tag_synonym(&self, name: &str, alias: &str) -> Result<bool, Error>;
+
+    /// Replaces `source` with `target` on every question at once, turns
+    /// `source` and its aliases into aliases of `target` and records the merge
+    async fn merge_tags(
+        &self,
+        source: &str,
+        target: &str,
+        account_id: &AccountId,
+    ) -> Result<TagMerge, Error>;
+
+    /// Recorded merges, newest first
+    async fn get_tag_merges(&self, limit: u32, offset: u32) -> Result<Vec<TagMerge>, Error>;
+}
+
+/// Storage operations on flags and the moderator actions resolving them
+#[async_trait]
+pub trait ModerationRepository: Send + Sync {
+    /// Fails with `NotFound` if the target is missing or deleted
+    /// and with a unique violation if the account has an open flag on it
+    async fn add_flag(
+        &self,
+        target: &FlagTarget,
+        flag: NewFlag,
+        account_id: &AccountId,
+    ) -> Result<Flag, Error>;
+
+    /// Posts with open flags, the most flagged first and otherwise the longest waiting
+    async fn get_moderation_queue(&self, limit: u32, offset: u32)
+    -> Result<Vec<QueueEntry>, Error>;
+
+    /// Applies the action to the target, deleted ones included,
+    /// resolves its open flags and records what was done
+    async fn moderate(
+        &self,
+        target: &FlagTarget,
+        action: NewModerationAction,
+        account_id: &AccountId,
+    ) -> Result<ModerationAction, Error>;
+
+    /// Recorded moderator actions, newest first
+    async fn get_moderation_log(
+        &self,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<ModerationAction>, Error>;
+
+    /// Fails with `NotFound` if the target does not exist, deleted ones included
+    async fn is_hidden(&self, target: &FlagTarget) -> Result<bool, Error>;
+}
+
+/// Storage operations on user accounts
+#[async_trait]
+pub trait AccountRepository: Send + Sync {
+    /// Stores a new account, the password has to be hashed already
+    async fn add_account(&self, account: Account) -> Result<Account, Error>;
+
+    async fn get_account(&self, email: &str) -> Result<Option<Account>, Error>;
+}
+
+/// Everything the route handlers need from a storage backend
+pub trait Repository:
+    QuestionRepository
+    + AnswerRepository
+    + CommentRepository
+    + TagRepository
+    + ModerationRepository
+    + AccountRepository
+    + Debug
+{
+}
+
+impl<T> Repository for T where
+    T: QuestionRepository
+        + AnswerRepository
+        + CommentRepository
+        + TagRepository
+        + ModerationRepository
+        + AccountRepository
+        + Debug
+{
+}
+
+/// The storage backend shared by all route handlers
+pub type Store = Arc<dyn Repository>;
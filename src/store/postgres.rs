@@ -0,0 +1,2031 @@
+use crate::types::account::{Account, AccountId, Role};
+use crate::types::answer::{Answer, AnswerId, NewAnswer, UpdateAnswer};
+use crate::types::comment::{Comment, CommentId, CommentTarget, NewComment};
+use crate::types::filter::{QuestionFilter, QuestionSort, TagMatch};
+use crate::types::moderation::{
+    Flag, FlagReason, FlagTarget, ModerationAction, ModerationActionKind, NewFlag,
+    NewModerationAction, QueueEntry,
+};
+use crate::types::pagination::{Cursor, Page, Pagination, Position};
+use crate::types::question::{
+    NewQuestion, Question, QuestionId, QuestionSummary, QuestionWithAnswers,
+};
+use crate::types::revision::{AnswerRevision, QuestionRevision};
+use crate::types::search::{MATCH_END, MATCH_START, SearchResult, mark_matches};
+use crate::types::tag::{Tag, TagMerge, TagSynonym, UpdateTag};
+use crate::types::vote::Vote;
+
+use super::{
+    AccountRepository, AnswerRepository, CommentRepository, ModerationRepository,
+    QuestionRepository, TagRepository,
+};
+
+use handle_errors::{Error, Resource};
+
+use async_trait::async_trait;
+
+use sqlx::postgres::{PgConnection, PgPool, PgPoolOptions, PgRow};
+use sqlx::types::Json;
+use sqlx::{Postgres, QueryBuilder, Row};
+
+#[derive(Clone, Debug)]
+pub struct PostgresStore {
+    pub connection: PgPool,
+}
+
+impl PostgresStore {
+    pub async fn new(db_url: &str, max_connections: u32) -> Self {
+        let db_pool = match PgPoolOptions::new()
+            .max_connections(max_connections)
+            .connect(db_url)
+            .await
+        {
+            Ok(pool) => pool,
+            Err(_) => panic!("Couldn't establish DB connection!"),
+        };
+
+        PostgresStore {
+            connection: db_pool,
+        }
+    }
+}
+
+fn to_question(row: PgRow) -> Question {
+    Question {
+        id: QuestionId(row.get("id")),
+        title: row.get("title"),
+        content: row.get("content"),
+        tags: row.get("tags"),
+        account_id: row.get::<Option<i32>, _>("account_id").map(AccountId),
+        created_on: row.get("created_on"),
+        updated_on: row.get("updated_on"),
+        score: row.get("score"),
+        accepted_answer_id: row.get::<Option<i32>, _>("accepted_answer_id").map(AnswerId),
+    }
+}
+
+fn to_answer(row: PgRow) -> Answer {
+    Answer {
+        id: AnswerId(row.get("id")),
+        content: row.get("content"),
+        question_id: QuestionId(row.get("question_id")),
+        account_id: row.get::<Option<i32>, _>("account_id").map(AccountId),
+        created_on: row.get("created_on"),
+        updated_on: row.get("updated_on"),
+        score: row.get("score"),
+    }
+}
+
+/// Where a row sits in a chronological listing, needs `id` and `created_on`
+fn to_position(row: &PgRow) -> Position {
+    Position {
+        created_on: row.get("created_on"),
+        id: row.get("id"),
+    }
+}
+
+/// Restricts a listing to the rows behind the cursor, given the columns
+/// and direction it is ordered by
+fn push_cursor(query: &mut QueryBuilder<Postgres>, columns: &str, descending: bool, cursor: &Cursor) {
+    let (operator, position) = match (cursor, descending) {
+        (Cursor::After(p), false) | (Cursor::Before(p), true) => (">", p),
+        (Cursor::After(p), true) | (Cursor::Before(p), false) => ("<", p),
+    };
+
+    query
+        .push(format!(" and ({}) {} (", columns, operator))
+        .push_bind(position.created_on)
+        .push(", ")
+        .push_bind(position.id)
+        .push(")");
+}
+
+/// Fetches one row more than the limit to tell whether another page follows
+fn push_page(query: &mut QueryBuilder<Postgres>, pagination: &Pagination) {
+    query
+        .push(" limit ")
+        .push_bind(pagination.limit as i64 + 1)
+        .push(" offset ")
+        .push_bind(pagination.offset as i64);
+}
+
+fn push_question_filter(query: &mut QueryBuilder<Postgres>, filter: &QuestionFilter) {
+    if !filter.tags.is_empty() {
+        query.push(match filter.tag_match {
+            TagMatch::Any => " and q.tags && ",
+            TagMatch::All => " and q.tags @> ",
+        });
+        query.push_bind(filter.tags.clone());
+    }
+
+    if let Some(after) = filter.created_after {
+        query.push(" and q.created_on > ").push_bind(after);
+    }
+
+    if let Some(before) = filter.created_before {
+        query.push(" and q.created_on < ").push_bind(before);
+    }
+
+    match filter.answered {
+        Some(true) => {
+            query.push(
+                " and exists (select 1 from answers a
+                where a.question_id = q.id and a.deleted_at is null)",
+            );
+        }
+        Some(false) => {
+            query.push(
+                " and not exists (select 1 from answers a
+                where a.question_id = q.id and a.deleted_at is null)",
+            );
+        }
+        None => {}
+    }
+}
+
+fn to_question_revision(row: PgRow) -> QuestionRevision {
+    QuestionRevision {
+        question_id: QuestionId(row.get("question_id")),
+        revision: row.get("revision"),
+        title: row.get("title"),
+        content: row.get("content"),
+        tags: row.get("tags"),
+        account_id: row.get::<Option<i32>, _>("account_id").map(AccountId),
+        created_on: row.get("created_on"),
+    }
+}
+
+fn to_answer_revision(row: PgRow) -> AnswerRevision {
+    AnswerRevision {
+        answer_id: AnswerId(row.get("answer_id")),
+        revision: row.get("revision"),
+        content: row.get("content"),
+        account_id: row.get::<Option<i32>, _>("account_id").map(AccountId),
+        created_on: row.get("created_on"),
+    }
+}
+
+fn to_comment(row: PgRow) -> Comment {
+    Comment {
+        id: CommentId(row.get("id")),
+        content: row.get("content"),
+        question_id: row.get::<Option<i32>, _>("question_id").map(QuestionId),
+        answer_id: row.get::<Option<i32>, _>("answer_id").map(AnswerId),
+        account_id: AccountId(row.get("account_id")),
+        created_on: row.get("created_on"),
+    }
+}
+
+fn to_tag(row: PgRow) -> Tag {
+    Tag {
+        name: row.get("name"),
+        description: row.get("description"),
+        question_count: row.get("question_count"),
+    }
+}
+
+fn to_tag_synonym(row: PgRow) -> TagSynonym {
+    TagSynonym {
+        alias: row.get("alias"),
+        tag: row.get("tag"),
+        account_id: row.get::<Option<i32>, _>("account_id").map(AccountId),
+        created_on: row.get("created_on"),
+    }
+}
+
+fn to_tag_merge(row: PgRow) -> TagMerge {
+    TagMerge {
+        id: row.get("id"),
+        source: row.get("source"),
+        target: row.get("target"),
+        account_id: row.get::<Option<i32>, _>("account_id").map(AccountId),
+        question_ids: row
+            .get::<Vec<i32>, _>("question_ids")
+            .into_iter()
+            .map(QuestionId)
+            .collect(),
+        created_on: row.get("created_on"),
+    }
+}
+
+// The tables check reasons and actions, any value they hold parses
+
+fn to_flag(row: PgRow) -> Flag {
+    Flag {
+        id: row.get("id"),
+        question_id: row.get::<Option<i32>, _>("question_id").map(QuestionId),
+        answer_id: row.get::<Option<i32>, _>("answer_id").map(AnswerId),
+        account_id: row.get::<Option<i32>, _>("account_id").map(AccountId),
+        reason: row.get::<&str, _>("reason").parse().expect("flag reason"),
+        details: row.get("details"),
+        created_on: row.get("created_on"),
+        resolved_on: row.get("resolved_on"),
+    }
+}
+
+fn to_queue_entry(row: PgRow) -> QueueEntry {
+    let mut reasons = row
+        .get::<Vec<String>, _>("reasons")
+        .iter()
+        .map(|reason| reason.parse().expect("flag reason"))
+        .collect::<Vec<FlagReason>>();
+    reasons.sort();
+
+    QueueEntry {
+        question_id: row.get::<Option<i32>, _>("question_id").map(QuestionId),
+        answer_id: row.get::<Option<i32>, _>("answer_id").map(AnswerId),
+        title: row.get("title"),
+        content: row.get("content"),
+        hidden: row.get("hidden"),
+        flag_count: row.get("flag_count"),
+        reasons,
+        first_flagged_on: row.get("first_flagged_on"),
+        last_flagged_on: row.get("last_flagged_on"),
+    }
+}
+
+fn to_moderation_action(row: PgRow) -> ModerationAction {
+    ModerationAction {
+        id: row.get("id"),
+        question_id: row.get::<Option<i32>, _>("question_id").map(QuestionId),
+        answer_id: row.get::<Option<i32>, _>("answer_id").map(AnswerId),
+        account_id: row.get::<Option<i32>, _>("account_id").map(AccountId),
+        action: row
+            .get::<&str, _>("action")
+            .parse()
+            .expect("moderation action"),
+        flag_ids: row.get("flag_ids"),
+        note: row.get("note"),
+        created_on: row.get("created_on"),
+    }
+}
+
+/// Table, flag column and resource of a moderated post
+fn flag_target(target: &FlagTarget) -> (&'static str, &'static str, Resource, i32) {
+    match target {
+        FlagTarget::Question(id) => ("questions", "question_id", Resource::Question, id.0),
+        FlagTarget::Answer(id) => ("answers", "answer_id", Resource::Answer, id.0),
+    }
+}
+
+/// Hides a post the content policy holds and raises a flag without an account for it,
+/// in the transaction the post is written in
+async fn hold_post(tx: &mut PgConnection, target: &FlagTarget, flag: NewFlag) -> Result<(), Error> {
+    let (table, _, _, id) = flag_target(target);
+    let (question_id, answer_id) = match target {
+        FlagTarget::Question(_) => (Some(id), None),
+        FlagTarget::Answer(_) => (None, Some(id)),
+    };
+
+    if let Err(e) = sqlx::query(&format!(
+        "update {} set hidden_at = now(), deleted_at = coalesce(deleted_at, now())
+        where id = $1",
+        table
+    ))
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    {
+        tracing::event!(tracing::Level::ERROR, "{:?}", e);
+        return Err(Error::from(e));
+    }
+
+    match sqlx::query(
+        "insert into flags (question_id, answer_id, reason, details)
+        values ($1, $2, $3, $4)",
+    )
+    .bind(question_id)
+    .bind(answer_id)
+    .bind(flag.reason.as_str())
+    .bind(flag.details)
+    .execute(&mut *tx)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", e);
+            Err(Error::from(e))
+        }
+    }
+}
+
+fn to_account(row: PgRow) -> Account {
+    Account {
+        id: Some(AccountId(row.get("id"))),
+        email: row.get("email"),
+        password: row.get("password"),
+        role: match row.get::<&str, _>("role") {
+            "moderator" => Role::Moderator,
+            _ => Role::User,
+        },
+    }
+}
+
+#[async_trait]
+impl QuestionRepository for PostgresStore {
+    async fn get_questions(
+        &self,
+        filter: &QuestionFilter,
+        pagination: &Pagination,
+    ) -> Result<Page<QuestionSummary>, Error> {
+        let mut count: QueryBuilder<Postgres> =
+            QueryBuilder::new("select count(*) from questions q where q.deleted_at is null");
+        push_question_filter(&mut count, filter);
+
+        let total = match count
+            .build()
+            .map(|row: PgRow| row.get::<i64, _>(0))
+            .fetch_one(&self.connection)
+            .await
+        {
+            Ok(total) => total,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        let mut query: QueryBuilder<Postgres> = QueryBuilder::new(
+            "select q.id, q.title, q.content, q.tags, q.account_id, q.created_on, q.updated_on, q.score,
+                q.accepted_answer_id,
+                (select count(*) from answers a
+                where a.question_id = q.id and a.deleted_at is null) as answer_count
+            from questions q where q.deleted_at is null",
+        );
+        push_question_filter(&mut query, filter);
+
+        let descending = filter.sort != QuestionSort::Oldest;
+        if let Some(cursor) = &pagination.cursor {
+            push_cursor(&mut query, "q.created_on, q.id", descending, cursor);
+        }
+
+        // Paging backwards walks the listing in reverse from the cursor
+        let backwards = matches!(pagination.cursor, Some(Cursor::Before(_)));
+        query.push(match (filter.sort, backwards) {
+            (QuestionSort::Newest, false) | (QuestionSort::Oldest, true) => {
+                " order by q.created_on desc, q.id desc"
+            }
+            (QuestionSort::Oldest, false) | (QuestionSort::Newest, true) => {
+                " order by q.created_on, q.id"
+            }
+            (QuestionSort::MostAnswers, false) => {
+                " order by answer_count desc, q.id desc"
+            }
+            (QuestionSort::MostAnswers, true) => {
+                " order by answer_count, q.id"
+            }
+            (QuestionSort::Score, false) => " order by q.score desc, q.id desc",
+            (QuestionSort::Score, true) => " order by q.score, q.id",
+            (QuestionSort::Title, false) => " order by q.title, q.id",
+            (QuestionSort::Title, true) => " order by q.title desc, q.id desc",
+        });
+        push_page(&mut query, pagination);
+
+        match query
+            .build()
+            .map(|row: PgRow| {
+                let position = to_position(&row);
+                let summary = QuestionSummary {
+                    answer_count: row.get("answer_count"),
+                    question: to_question(row),
+                };
+                (position, summary)
+            })
+            .fetch_all(&self.connection)
+            .await
+        {
+            Ok(rows) => Ok(Page::from_rows(rows, total, pagination)),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn get_question(&self, question_id: i32) -> Result<Question, Error> {
+        match sqlx::query("select * from questions where id = $1 and deleted_at is null")
+            .bind(question_id)
+            .map(to_question)
+            .fetch_one(&self.connection)
+            .await
+        {
+            Ok(question) => Ok(question),
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound {
+                resource: Resource::Question,
+                id: question_id,
+            }),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn get_question_with_answers(
+        &self,
+        question_id: i32,
+    ) -> Result<QuestionWithAnswers, Error> {
+        match sqlx::query(
+            "select q.id, q.title, q.content, q.tags, q.account_id, q.created_on, q.updated_on, q.score,
+                q.accepted_answer_id,
+                coalesce(
+                    json_agg(json_build_object(
+                        'id', a.id,
+                        'content', a.content,
+                        'question_id', a.question_id,
+                        'account_id', a.account_id,
+                        'created_on', a.created_on,
+                        'updated_on', a.updated_on,
+                        'score', a.score
+                    ) order by a.id is distinct from q.accepted_answer_id, a.created_on, a.id)
+                    filter (where a.id is not null),
+                    '[]'
+                ) as answers
+            from questions q
+            left join answers a on a.question_id = q.id and a.deleted_at is null
+            where q.id = $1 and q.deleted_at is null
+            group by q.id",
+        )
+        .bind(question_id)
+        .map(|row: PgRow| {
+            let answers = row.get::<Json<Vec<Answer>>, _>("answers").0;
+            QuestionWithAnswers {
+                answer_count: answers.len() as i64,
+                answers,
+                question: to_question(row),
+            }
+        })
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(question) => Ok(question),
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound {
+                resource: Resource::Question,
+                id: question_id,
+            }),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn add_question(
+        &self,
+        question: NewQuestion,
+        account_id: &AccountId,
+        hold: Option<NewFlag>,
+    ) -> Result<Question, Error> {
+        let mut tx = match self.connection.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        let question = match sqlx::query(
+            "with inserted as (
+                insert into questions (title, content, tags, account_id) values ($1, $2, $3, $4)
+                returning id, title, content, tags, account_id, created_on, updated_on, score,
+                    accepted_answer_id
+            ), revision as (
+                insert into question_revisions
+                    (question_id, revision, title, content, tags, account_id, created_on)
+                select id, 1, title, content, tags, account_id, created_on from inserted
+            )
+            select * from inserted",
+        )
+        .bind(question.title)
+        .bind(question.content)
+        .bind(question.tags)
+        .bind(account_id.0)
+        .map(to_question)
+        .fetch_one(&mut *tx)
+        .await
+        {
+            Ok(question) => question,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        if let Some(flag) = hold {
+            hold_post(&mut tx, &FlagTarget::Question(question.id.clone()), flag).await?;
+        }
+
+        match tx.commit().await {
+            Ok(_) => Ok(question),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn update_question(
+        &self,
+        question: NewQuestion,
+        question_id: i32,
+        account_id: &AccountId,
+        hold: Option<NewFlag>,
+    ) -> Result<Question, Error> {
+        let mut tx = match self.connection.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        // Concurrent edits wait for this one, the statement numbering
+        // the revision only starts once the row is locked and sees their revisions
+        match sqlx::query("select id from questions where id = $1 and deleted_at is null for update")
+            .bind(question_id)
+            .fetch_optional(&mut *tx)
+            .await
+        {
+            Ok(Some(_)) => (),
+            Ok(None) => {
+                return Err(Error::NotFound {
+                    resource: Resource::Question,
+                    id: question_id,
+                });
+            }
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        }
+
+        let question = match sqlx::query(
+            "with updated as (
+                update questions set title = $1, content = $2, tags = $3, updated_on = now()
+                where id = $4 and deleted_at is null
+                returning id, title, content, tags, account_id, created_on, updated_on, score,
+                    accepted_answer_id
+            ), revision as (
+                insert into question_revisions
+                    (question_id, revision, title, content, tags, account_id, created_on)
+                select id,
+                    (select coalesce(max(revision), 0) + 1 from question_revisions
+                    where question_id = $4),
+                    title, content, tags, $5, updated_on
+                from updated
+            )
+            select * from updated",
+        )
+        .bind(question.title)
+        .bind(question.content)
+        .bind(question.tags)
+        .bind(question_id)
+        .bind(account_id.0)
+        .map(to_question)
+        .fetch_one(&mut *tx)
+        .await
+        {
+            Ok(question) => question,
+            Err(sqlx::Error::RowNotFound) => {
+                return Err(Error::NotFound {
+                    resource: Resource::Question,
+                    id: question_id,
+                });
+            }
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        if let Some(flag) = hold {
+            hold_post(&mut tx, &FlagTarget::Question(question.id.clone()), flag).await?;
+        }
+
+        match tx.commit().await {
+            Ok(_) => Ok(question),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn delete_question(&self, question_id: i32) -> Result<bool, Error> {
+        match sqlx::query(
+            "update questions set deleted_at = now() where id = $1 and deleted_at is null",
+        )
+        .bind(question_id)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(res) if res.rows_affected() == 0 => Err(Error::NotFound {
+                resource: Resource::Question,
+                id: question_id,
+            }),
+            Ok(_) => Ok(true),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn restore_question(&self, question_id: i32) -> Result<Question, Error> {
+        match sqlx::query(
+            "update questions set deleted_at = null, hidden_at = null
+            where id = $1 and deleted_at is not null
+            returning id, title, content, tags, account_id, created_on, updated_on, score,
+                accepted_answer_id",
+        )
+        .bind(question_id)
+        .map(to_question)
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(question) => Ok(question),
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound {
+                resource: Resource::Question,
+                id: question_id,
+            }),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn purge_question(&self, question_id: i32) -> Result<bool, Error> {
+        let mut tx = match self.connection.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        // Revisions go along with their answers and question
+        let purged = match sqlx::query("delete from answers where question_id = $1")
+            .bind(question_id)
+            .execute(&mut *tx)
+            .await
+        {
+            Ok(_) => {
+                sqlx::query("delete from questions where id = $1")
+                    .bind(question_id)
+                    .execute(&mut *tx)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+
+        match purged {
+            Ok(res) if res.rows_affected() == 0 => Err(Error::NotFound {
+                resource: Resource::Question,
+                id: question_id,
+            }),
+            Ok(_) => match tx.commit().await {
+                Ok(_) => Ok(true),
+                Err(e) => {
+                    tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                    Err(Error::from(e))
+                }
+            },
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn vote_question(
+        &self,
+        question_id: i32,
+        account_id: &AccountId,
+        vote: Vote,
+    ) -> Result<Question, Error> {
+        // The score follows the votes through a trigger
+        match sqlx::query(
+            "insert into votes (account_id, question_id, value)
+            select $1, id, $3 from questions where id = $2 and deleted_at is null
+            on conflict (question_id, account_id) where question_id is not null
+            do update set value = excluded.value",
+        )
+        .bind(account_id.0)
+        .bind(question_id)
+        .bind(vote.value())
+        .execute(&self.connection)
+        .await
+        {
+            Ok(res) if res.rows_affected() == 0 => Err(Error::NotFound {
+                resource: Resource::Question,
+                id: question_id,
+            }),
+            Ok(_) => self.get_question(question_id).await,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn accept_answer(&self, question_id: i32, answer_id: i32) -> Result<Question, Error> {
+        match sqlx::query(
+            "update questions set accepted_answer_id = $2
+            where id = $1 and deleted_at is null and exists (
+                select 1 from answers
+                where id = $2 and question_id = $1 and deleted_at is null
+            )
+            returning id, title, content, tags, account_id, created_on, updated_on, score,
+                accepted_answer_id",
+        )
+        .bind(question_id)
+        .bind(answer_id)
+        .map(to_question)
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(question) => Ok(question),
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound {
+                resource: Resource::Answer,
+                id: answer_id,
+            }),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn get_question_revisions(
+        &self,
+        question_id: i32,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<QuestionRevision>, Error> {
+        let revisions = match sqlx::query(
+            "select question_id, revision, title, content, tags, account_id, created_on
+            from question_revisions
+            where question_id = $1
+                and exists (select 1 from questions where id = $1 and deleted_at is null)
+            order by revision
+            limit $2 offset $3",
+        )
+        .bind(question_id)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .map(to_question_revision)
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(revisions) => revisions,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        // Every question has a first revision, an empty first page means it is missing
+        // or deleted, later pages are empty past the last revision as well
+        if revisions.is_empty() {
+            match offset {
+                0 => {
+                    return Err(Error::NotFound {
+                        resource: Resource::Question,
+                        id: question_id,
+                    });
+                }
+                _ => {
+                    self.get_question(question_id).await?;
+                }
+            }
+        }
+
+        Ok(revisions)
+    }
+
+    async fn get_question_revision(
+        &self,
+        question_id: i32,
+        revision: i32,
+    ) -> Result<QuestionRevision, Error> {
+        match sqlx::query(
+            "select question_id, revision, title, content, tags, account_id, created_on
+            from question_revisions where question_id = $1 and revision = $2",
+        )
+        .bind(question_id)
+        .bind(revision)
+        .map(to_question_revision)
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(revision) => Ok(revision),
+            Err(sqlx::Error::RowNotFound) => {
+                // Tell a missing revision apart from a missing question
+                self.get_question(question_id).await?;
+                Err(Error::NotFound {
+                    resource: Resource::Revision,
+                    id: revision,
+                })
+            }
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn search_questions(
+        &self,
+        query: &str,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<SearchResult>, Error> {
+        match sqlx::query(
+            "select q.id, q.title, q.content, q.tags, q.account_id, q.created_on, q.updated_on, q.score,
+                q.accepted_answer_id,
+                ts_rank(q.search, query) as rank,
+                ts_headline('english',
+                    translate(q.title || ' ' || q.content || ' ' || coalesce(
+                        (select string_agg(a.content, ' ') from answers a
+                        where a.question_id = q.id and a.deleted_at is null),
+                        ''), $4, ''),
+                    query,
+                    $5
+                ) as snippet
+            from questions q, websearch_to_tsquery('english', $1) query
+            where q.search @@ query and q.deleted_at is null
+            order by rank desc, q.id desc
+            limit $2 offset $3",
+        )
+        .bind(query)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .bind(format!("{}{}", MATCH_START, MATCH_END))
+        .bind(format!(
+            "StartSel={}, StopSel={}, MaxFragments=2, MaxWords=30, MinWords=10",
+            MATCH_START, MATCH_END
+        ))
+        .map(|row: PgRow| SearchResult {
+            rank: row.get("rank"),
+            snippet: mark_matches(row.get("snippet")),
+            question: to_question(row),
+        })
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(results) => Ok(results),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn is_question_owner(
+        &self,
+        question_id: i32,
+        account_id: &AccountId,
+    ) -> Result<bool, Error> {
+        match sqlx::query("select account_id from questions where id = $1")
+            .bind(question_id)
+            .map(|row: PgRow| row.get::<Option<i32>, _>("account_id"))
+            .fetch_one(&self.connection)
+            .await
+        {
+            Ok(owner) => Ok(owner == Some(account_id.0)),
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound {
+                resource: Resource::Question,
+                id: question_id,
+            }),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl AnswerRepository for PostgresStore {
+    async fn get_answers(
+        &self,
+        question_id: i32,
+        pagination: &Pagination,
+    ) -> Result<Page<Answer>, Error> {
+        // Tell an empty listing apart from a missing question
+        self.get_question(question_id).await?;
+
+        let total = match sqlx::query(
+            "select count(*) from answers where question_id = $1 and deleted_at is null",
+        )
+            .bind(question_id)
+            .map(|row: PgRow| row.get::<i64, _>(0))
+            .fetch_one(&self.connection)
+            .await
+        {
+            Ok(total) => total,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        let mut query: QueryBuilder<Postgres> = QueryBuilder::new(
+            "select a.id, a.content, a.question_id, a.account_id, a.created_on, a.updated_on,
+                a.score
+            from answers a join questions q on q.id = a.question_id
+            where a.deleted_at is null and a.question_id = ",
+        );
+        query.push_bind(question_id);
+
+        // The accepted answer is pinned to the top, so whether the cursor
+        // points at it ranks before its position
+        if let Some(cursor) = &pagination.cursor {
+            let (operator, position) = match cursor {
+                Cursor::After(p) => (">", p),
+                Cursor::Before(p) => ("<", p),
+            };
+            query
+                .push(format!(
+                    " and (a.id is distinct from q.accepted_answer_id, a.created_on, a.id) {} (",
+                    operator
+                ))
+                .push_bind(position.id)
+                .push(" is distinct from q.accepted_answer_id, ")
+                .push_bind(position.created_on)
+                .push(", ")
+                .push_bind(position.id)
+                .push(")");
+        }
+
+        query.push(match pagination.cursor {
+            Some(Cursor::Before(_)) => {
+                " order by a.id is distinct from q.accepted_answer_id desc,
+                    a.created_on desc, a.id desc"
+            }
+            _ => " order by a.id is distinct from q.accepted_answer_id, a.created_on, a.id",
+        });
+        push_page(&mut query, pagination);
+
+        match query
+            .build()
+            .map(|row: PgRow| (to_position(&row), to_answer(row)))
+            .fetch_all(&self.connection)
+            .await
+        {
+            Ok(rows) => Ok(Page::from_rows(rows, total, pagination)),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn get_answer(&self, answer_id: i32) -> Result<Answer, Error> {
+        match sqlx::query(
+            "select a.id, a.content, a.question_id, a.account_id, a.created_on, a.updated_on, a.score
+            from answers a join questions q on q.id = a.question_id
+            where a.id = $1 and a.deleted_at is null and q.deleted_at is null",
+        )
+        .bind(answer_id)
+        .map(to_answer)
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(answer) => Ok(answer),
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound {
+                resource: Resource::Answer,
+                id: answer_id,
+            }),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn add_answer(
+        &self,
+        answer: NewAnswer,
+        account_id: &AccountId,
+        hold: Option<NewFlag>,
+    ) -> Result<Answer, Error> {
+        let mut tx = match self.connection.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        let answer = match sqlx::query(
+            "with inserted as (
+                insert into answers (content, question_id, account_id)
+                select $1, $2, $3
+                where not exists (
+                    select 1 from questions where id = $2 and deleted_at is not null
+                )
+                returning id, content, question_id, account_id, created_on, updated_on, score
+            ), revision as (
+                insert into answer_revisions (answer_id, revision, content, account_id, created_on)
+                select id, 1, content, account_id, created_on from inserted
+            )
+            select * from inserted",
+        )
+        .bind(answer.content)
+        .bind(answer.question_id.0)
+        .bind(account_id.0)
+        .map(to_answer)
+        .fetch_one(&mut *tx)
+        .await
+        {
+            Ok(answer) => answer,
+            // Nothing is inserted for deleted questions
+            Err(sqlx::Error::RowNotFound) => {
+                return Err(Error::NotFound {
+                    resource: Resource::Question,
+                    id: answer.question_id.0,
+                });
+            }
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        if let Some(flag) = hold {
+            hold_post(&mut tx, &FlagTarget::Answer(answer.id.clone()), flag).await?;
+        }
+
+        match tx.commit().await {
+            Ok(_) => Ok(answer),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn update_answer(
+        &self,
+        answer: UpdateAnswer,
+        answer_id: i32,
+        account_id: &AccountId,
+        hold: Option<NewFlag>,
+    ) -> Result<Answer, Error> {
+        let mut tx = match self.connection.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        // Concurrent edits wait for this one, the statement numbering
+        // the revision only starts once the row is locked and sees their revisions
+        match sqlx::query("select id from answers where id = $1 and deleted_at is null for update")
+            .bind(answer_id)
+            .fetch_optional(&mut *tx)
+            .await
+        {
+            Ok(Some(_)) => (),
+            Ok(None) => {
+                return Err(Error::NotFound {
+                    resource: Resource::Answer,
+                    id: answer_id,
+                });
+            }
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        }
+
+        let answer = match sqlx::query(
+            "with updated as (
+                update answers set content = $1, updated_on = now()
+                where id = $2 and deleted_at is null
+                returning id, content, question_id, account_id, created_on, updated_on, score
+            ), revision as (
+                insert into answer_revisions (answer_id, revision, content, account_id, created_on)
+                select id,
+                    (select coalesce(max(revision), 0) + 1 from answer_revisions
+                    where answer_id = $2),
+                    content, $3, updated_on
+                from updated
+            )
+            select * from updated",
+        )
+        .bind(answer.content)
+        .bind(answer_id)
+        .bind(account_id.0)
+        .map(to_answer)
+        .fetch_one(&mut *tx)
+        .await
+        {
+            Ok(answer) => answer,
+            Err(sqlx::Error::RowNotFound) => {
+                return Err(Error::NotFound {
+                    resource: Resource::Answer,
+                    id: answer_id,
+                });
+            }
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        if let Some(flag) = hold {
+            hold_post(&mut tx, &FlagTarget::Answer(answer.id.clone()), flag).await?;
+        }
+
+        match tx.commit().await {
+            Ok(_) => Ok(answer),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn delete_answer(&self, answer_id: i32) -> Result<bool, Error> {
+        match sqlx::query(
+            "update answers set deleted_at = now() where id = $1 and deleted_at is null",
+        )
+        .bind(answer_id)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(res) if res.rows_affected() == 0 => Err(Error::NotFound {
+                resource: Resource::Answer,
+                id: answer_id,
+            }),
+            Ok(_) => Ok(true),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn restore_answer(&self, answer_id: i32) -> Result<Answer, Error> {
+        match sqlx::query(
+            "update answers set deleted_at = null, hidden_at = null
+            where id = $1 and deleted_at is not null
+            returning id, content, question_id, account_id, created_on, updated_on, score",
+        )
+        .bind(answer_id)
+        .map(to_answer)
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(answer) => Ok(answer),
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound {
+                resource: Resource::Answer,
+                id: answer_id,
+            }),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn vote_answer(
+        &self,
+        answer_id: i32,
+        account_id: &AccountId,
+        vote: Vote,
+    ) -> Result<Answer, Error> {
+        // The score follows the votes through a trigger
+        match sqlx::query(
+            "insert into votes (account_id, answer_id, value)
+            select $1, a.id, $3 from answers a
+            join questions q on q.id = a.question_id
+            where a.id = $2 and a.deleted_at is null and q.deleted_at is null
+            on conflict (answer_id, account_id) where answer_id is not null
+            do update set value = excluded.value",
+        )
+        .bind(account_id.0)
+        .bind(answer_id)
+        .bind(vote.value())
+        .execute(&self.connection)
+        .await
+        {
+            Ok(res) if res.rows_affected() == 0 => Err(Error::NotFound {
+                resource: Resource::Answer,
+                id: answer_id,
+            }),
+            Ok(_) => self.get_answer(answer_id).await,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn get_answer_revisions(
+        &self,
+        answer_id: i32,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<AnswerRevision>, Error> {
+        let revisions = match sqlx::query(
+            "select answer_id, revision, content, account_id, created_on
+            from answer_revisions
+            where answer_id = $1
+                and exists (select 1 from answers where id = $1 and deleted_at is null)
+            order by revision
+            limit $2 offset $3",
+        )
+        .bind(answer_id)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .map(to_answer_revision)
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(revisions) => revisions,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        // Every answer has a first revision, an empty first page means it is missing
+        // or deleted, later pages are empty past the last revision as well
+        if revisions.is_empty() {
+            match offset {
+                0 => {
+                    return Err(Error::NotFound {
+                        resource: Resource::Answer,
+                        id: answer_id,
+                    });
+                }
+                _ => {
+                    self.get_answer(answer_id).await?;
+                }
+            }
+        }
+
+        Ok(revisions)
+    }
+
+    async fn get_answer_revision(
+        &self,
+        answer_id: i32,
+        revision: i32,
+    ) -> Result<AnswerRevision, Error> {
+        match sqlx::query(
+            "select answer_id, revision, content, account_id, created_on
+            from answer_revisions where answer_id = $1 and revision = $2",
+        )
+        .bind(answer_id)
+        .bind(revision)
+        .map(to_answer_revision)
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(revision) => Ok(revision),
+            Err(sqlx::Error::RowNotFound) => {
+                // Tell a missing revision apart from a missing answer
+                self.get_answer(answer_id).await?;
+                Err(Error::NotFound {
+                    resource: Resource::Revision,
+                    id: revision,
+                })
+            }
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn is_answer_owner(&self, answer_id: i32, account_id: &AccountId) -> Result<bool, Error> {
+        match sqlx::query("select account_id from answers where id = $1")
+            .bind(answer_id)
+            .map(|row: PgRow| row.get::<Option<i32>, _>("account_id"))
+            .fetch_one(&self.connection)
+            .await
+        {
+            Ok(owner) => Ok(owner == Some(account_id.0)),
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound {
+                resource: Resource::Answer,
+                id: answer_id,
+            }),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CommentRepository for PostgresStore {
+    async fn get_comments(&self, target: &CommentTarget) -> Result<Vec<Comment>, Error> {
+        // Tell an empty listing apart from a missing or deleted target
+        let query = match target {
+            CommentTarget::Question(id) => {
+                self.get_question(id.0).await?;
+                sqlx::query(
+                    "select id, content, question_id, answer_id, account_id, created_on
+                    from comments where question_id = $1 order by created_on, id",
+                )
+                .bind(id.0)
+            }
+            CommentTarget::Answer(id) => {
+                self.get_answer(id.0).await?;
+                sqlx::query(
+                    "select id, content, question_id, answer_id, account_id, created_on
+                    from comments where answer_id = $1 order by created_on, id",
+                )
+                .bind(id.0)
+            }
+        };
+
+        match query.map(to_comment).fetch_all(&self.connection).await {
+            Ok(comments) => Ok(comments),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn add_comment(
+        &self,
+        target: &CommentTarget,
+        comment: NewComment,
+        account_id: &AccountId,
+    ) -> Result<Comment, Error> {
+        // Deleted questions and answers take no new comments
+        let (question_id, answer_id) = match target {
+            CommentTarget::Question(id) => (Some(self.get_question(id.0).await?.id.0), None),
+            CommentTarget::Answer(id) => (None, Some(self.get_answer(id.0).await?.id.0)),
+        };
+
+        match sqlx::query(
+            "insert into comments (content, question_id, answer_id, account_id)
+            values ($1, $2, $3, $4)
+            returning id, content, question_id, answer_id, account_id, created_on",
+        )
+        .bind(comment.content)
+        .bind(question_id)
+        .bind(answer_id)
+        .bind(account_id.0)
+        .map(to_comment)
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(comment) => Ok(comment),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn delete_comment(&self, comment_id: i32) -> Result<bool, Error> {
+        match sqlx::query("delete from comments where id = $1")
+            .bind(comment_id)
+            .execute(&self.connection)
+            .await
+        {
+            Ok(res) if res.rows_affected() == 0 => Err(Error::NotFound {
+                resource: Resource::Comment,
+                id: comment_id,
+            }),
+            Ok(_) => Ok(true),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn is_comment_owner(
+        &self,
+        comment_id: i32,
+        account_id: &AccountId,
+    ) -> Result<bool, Error> {
+        match sqlx::query("select account_id from comments where id = $1")
+            .bind(comment_id)
+            .map(|row: PgRow| row.get::<i32, _>("account_id"))
+            .fetch_one(&self.connection)
+            .await
+        {
+            Ok(owner) => Ok(owner == account_id.0),
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound {
+                resource: Resource::Comment,
+                id: comment_id,
+            }),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl TagRepository for PostgresStore {
+    async fn get_tags(&self, limit: u32, offset: u32) -> Result<Vec<Tag>, Error> {
+        match sqlx::query(
+            "select t.name, t.description, count(q.id) as question_count
+            from tags t
+            left join question_tags qt on qt.tag_id = t.id
+            left join questions q on q.id = qt.question_id and q.deleted_at is null
+            group by t.id
+            order by question_count desc, t.name
+            limit $1 offset $2",
+        )
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .map(to_tag)
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(tags) => Ok(tags),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn get_tag(&self, name: &str) -> Result<Tag, Error> {
+        match sqlx::query(
+            "select t.name, t.description, count(q.id) as question_count
+            from tags t
+            left join question_tags qt on qt.tag_id = t.id
+            left join questions q on q.id = qt.question_id and q.deleted_at is null
+            where t.name = $1
+            group by t.id",
+        )
+        .bind(name)
+        .map(to_tag)
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(tag) => Ok(tag),
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFoundByName {
+                resource: Resource::Tag,
+                name: name.to_string(),
+            }),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn update_tag(&self, name: &str, tag: UpdateTag) -> Result<Tag, Error> {
+        match sqlx::query("update tags set description = $1 where name = $2")
+            .bind(tag.description)
+            .bind(name)
+            .execute(&self.connection)
+            .await
+        {
+            Ok(res) if res.rows_affected() == 0 => Err(Error::NotFoundByName {
+                resource: Resource::Tag,
+                name: name.to_string(),
+            }),
+            Ok(_) => self.get_tag(name).await,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn resolve_tag_synonyms(&self, tags: Vec<String>) -> Result<Vec<String>, Error> {
+        match sqlx::query(
+            "select coalesce(t.name, given.tag) as tag
+            from unnest($1::text[]) with ordinality as given(tag, position)
+            left join tag_synonyms s on s.alias = given.tag
+            left join tags t on t.id = s.tag_id
+            order by given.position",
+        )
+        .bind(tags)
+        .map(|row: PgRow| row.get::<String, _>("tag"))
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(resolved) => {
+                let mut tags: Vec<String> = Vec::with_capacity(resolved.len());
+                for tag in resolved {
+                    if !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                }
+                Ok(tags)
+            }
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn get_tag_synonyms(&self, name: &str) -> Result<Vec<TagSynonym>, Error> {
+        // Tell an empty listing apart from a missing tag
+        self.get_tag(name).await?;
+
+        match sqlx::query(
+            "select s.alias, t.name as tag, s.account_id, s.created_on
+            from tag_synonyms s join tags t on t.id = s.tag_id
+            where t.name = $1
+            order by s.alias",
+        )
+        .bind(name)
+        .map(to_tag_synonym)
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(synonyms) => Ok(synonyms),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn add_tag_synonym(
+        &self,
+        name: &str,
+        alias: &str,
+        account_id: &AccountId,
+    ) -> Result<TagSynonym, Error> {
+        match sqlx::query(
+            "insert into tag_synonyms (alias, tag_id, account_id)
+            select $1, id, $3 from tags where name = $2
+            returning alias, $2 as tag, account_id, created_on",
+        )
+        .bind(alias)
+        .bind(name)
+        .bind(account_id.0)
+        .map(to_tag_synonym)
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(synonym) => Ok(synonym),
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFoundByName {
+                resource: Resource::Tag,
+                name: name.to_string(),
+            }),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn delete_tag_synonym(&self, name: &str, alias: &str) -> Result<bool, Error> {
+        match sqlx::query(
+            "delete from tag_synonyms s using tags t
+            where t.id = s.tag_id and t.name = $1 and s.alias = $2",
+        )
+        .bind(name)
+        .bind(alias)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(res) if res.rows_affected() == 0 => Err(Error::NotFoundByName {
+                resource: Resource::TagSynonym,
+                name: alias.to_string(),
+            }),
+            Ok(_) => Ok(true),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn merge_tags(
+        &self,
+        source: &str,
+        target: &str,
+        account_id: &AccountId,
+    ) -> Result<TagMerge, Error> {
+        let mut tx = match self.connection.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        // Locks both tags, so concurrent merges of either one wait for this one
+        let tags = match sqlx::query("select id, name from tags where name in ($1, $2) for update")
+            .bind(source)
+            .bind(target)
+            .map(|row: PgRow| (row.get::<String, _>("name"), row.get::<i32, _>("id")))
+            .fetch_all(&mut *tx)
+            .await
+        {
+            Ok(tags) => tags,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+        let id_of = |name: &str| tags.iter().find(|(n, _)| n == name).map(|(_, id)| *id);
+
+        let (source_id, target_id) = match (id_of(source), id_of(target)) {
+            (Some(source_id), Some(target_id)) => (source_id, target_id),
+            (None, _) => {
+                return Err(Error::NotFoundByName {
+                    resource: Resource::Tag,
+                    name: source.to_string(),
+                });
+            }
+            (_, None) => {
+                return Err(Error::NotFoundByName {
+                    resource: Resource::Tag,
+                    name: target.to_string(),
+                });
+            }
+        };
+
+        // Questions carrying both tags keep only the target,
+        // the trigger on `questions.tags` updates `question_tags`
+        let rewritten = sqlx::query(
+            "update questions set tags = case
+                when $2 = any(tags) then array_remove(tags, $1)
+                else array_replace(tags, $1, $2)
+            end
+            where $1 = any(tags)
+            returning id",
+        )
+        .bind(source)
+        .bind(target)
+        .map(|row: PgRow| row.get::<i32, _>("id"))
+        .fetch_all(&mut *tx)
+        .await;
+
+        let question_ids = match rewritten {
+            Ok(mut question_ids) => {
+                question_ids.sort();
+                question_ids
+            }
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        // Aliases of the source move over before the source is dropped,
+        // the source itself becomes one of them
+        let aliased = match sqlx::query("update tag_synonyms set tag_id = $2 where tag_id = $1")
+            .bind(source_id)
+            .bind(target_id)
+            .execute(&mut *tx)
+            .await
+        {
+            Ok(_) => {
+                sqlx::query("delete from tags where id = $1")
+                    .bind(source_id)
+                    .execute(&mut *tx)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        let aliased = match aliased {
+            Ok(_) => {
+                sqlx::query("insert into tag_synonyms (alias, tag_id, account_id) values ($1, $2, $3)")
+                    .bind(source)
+                    .bind(target_id)
+                    .bind(account_id.0)
+                    .execute(&mut *tx)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = aliased {
+            tracing::event!(tracing::Level::ERROR, "{:?}", e);
+            return Err(Error::from(e));
+        }
+
+        let merge = match sqlx::query(
+            "insert into tag_merges (source, target, account_id, question_ids)
+            values ($1, $2, $3, $4)
+            returning id, source, target, account_id, question_ids, created_on",
+        )
+        .bind(source)
+        .bind(target)
+        .bind(account_id.0)
+        .bind(&question_ids)
+        .map(to_tag_merge)
+        .fetch_one(&mut *tx)
+        .await
+        {
+            Ok(merge) => merge,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        match tx.commit().await {
+            Ok(_) => Ok(merge),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn get_tag_merges(&self, limit: u32, offset: u32) -> Result<Vec<TagMerge>, Error> {
+        match sqlx::query(
+            "select id, source, target, account_id, question_ids, created_on
+            from tag_merges
+            order by created_on desc, id desc
+            limit $1 offset $2",
+        )
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .map(to_tag_merge)
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(merges) => Ok(merges),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ModerationRepository for PostgresStore {
+    async fn add_flag(
+        &self,
+        target: &FlagTarget,
+        flag: NewFlag,
+        account_id: &AccountId,
+    ) -> Result<Flag, Error> {
+        // Deleted and hidden posts are out of sight, nobody can flag them
+        let (question_id, answer_id) = match target {
+            FlagTarget::Question(id) => (Some(self.get_question(id.0).await?.id.0), None),
+            FlagTarget::Answer(id) => (None, Some(self.get_answer(id.0).await?.id.0)),
+        };
+
+        match sqlx::query(
+            "insert into flags (question_id, answer_id, account_id, reason, details)
+            values ($1, $2, $3, $4, $5)
+            returning id, question_id, answer_id, account_id, reason, details, created_on,
+                resolved_on",
+        )
+        .bind(question_id)
+        .bind(answer_id)
+        .bind(account_id.0)
+        .bind(flag.reason.as_str())
+        .bind(flag.details)
+        .map(to_flag)
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(flag) => Ok(flag),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn get_moderation_queue(
+        &self,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<QueueEntry>, Error> {
+        match sqlx::query(
+            "select f.question_id, f.answer_id, q.title, coalesce(q.content, a.content) as content,
+                coalesce(q.hidden_at, a.hidden_at) is not null as hidden,
+                count(*) as flag_count, array_agg(distinct f.reason) as reasons,
+                min(f.created_on) as first_flagged_on, max(f.created_on) as last_flagged_on
+            from flags f
+            left join questions q on q.id = f.question_id
+            left join answers a on a.id = f.answer_id
+            where f.resolved_on is null
+            group by f.question_id, f.answer_id, q.id, a.id
+            order by flag_count desc, first_flagged_on, f.question_id, f.answer_id
+            limit $1 offset $2",
+        )
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .map(to_queue_entry)
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(entries) => Ok(entries),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn moderate(
+        &self,
+        target: &FlagTarget,
+        action: NewModerationAction,
+        account_id: &AccountId,
+    ) -> Result<ModerationAction, Error> {
+        let (table, column, resource, id) = flag_target(target);
+
+        let mut tx = match self.connection.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        // Locks the post, so flags added meanwhile wait for the next action
+        match sqlx::query(&format!("select id from {} where id = $1 for update", table))
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await
+        {
+            Ok(_) => (),
+            Err(sqlx::Error::RowNotFound) => return Err(Error::NotFound { resource, id }),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        }
+
+        // Dismissing only brings back hidden posts, a post deleted before it was
+        // hidden was deleted by its author or a moderator and stays deleted.
+        // Hiding keeps the time of the first hide for that comparison
+        let change = match action.action {
+            ModerationActionKind::Dismiss => {
+                "set deleted_at = case when deleted_at < hidden_at then deleted_at end,
+                    hidden_at = null
+                where id = $1 and hidden_at is not null"
+            }
+            ModerationActionKind::Hide => {
+                "set hidden_at = coalesce(hidden_at, now()), deleted_at = coalesce(deleted_at, now())
+                where id = $1"
+            }
+            ModerationActionKind::Delete => {
+                "set hidden_at = null, deleted_at = coalesce(deleted_at, now()) where id = $1"
+            }
+        };
+        let resolved = match sqlx::query(&format!("update {} {}", table, change))
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+        {
+            Ok(_) => {
+                sqlx::query(&format!(
+                    "update flags set resolved_on = now()
+                    where {} = $1 and resolved_on is null
+                    returning id",
+                    column
+                ))
+                .bind(id)
+                .map(|row: PgRow| row.get::<i32, _>("id"))
+                .fetch_all(&mut *tx)
+                .await
+            }
+            Err(e) => Err(e),
+        };
+
+        let flag_ids = match resolved {
+            Ok(mut flag_ids) => {
+                flag_ids.sort();
+                flag_ids
+            }
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        let (question_id, answer_id) = match target {
+            FlagTarget::Question(_) => (Some(id), None),
+            FlagTarget::Answer(_) => (None, Some(id)),
+        };
+
+        let recorded = match sqlx::query(
+            "insert into moderation_actions
+                (question_id, answer_id, account_id, action, flag_ids, note)
+            values ($1, $2, $3, $4, $5, $6)
+            returning id, question_id, answer_id, account_id, action, flag_ids, note, created_on",
+        )
+        .bind(question_id)
+        .bind(answer_id)
+        .bind(account_id.0)
+        .bind(action.action.as_str())
+        .bind(&flag_ids)
+        .bind(action.note)
+        .map(to_moderation_action)
+        .fetch_one(&mut *tx)
+        .await
+        {
+            Ok(recorded) => recorded,
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                return Err(Error::from(e));
+            }
+        };
+
+        match tx.commit().await {
+            Ok(_) => Ok(recorded),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn get_moderation_log(
+        &self,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<ModerationAction>, Error> {
+        match sqlx::query(
+            "select id, question_id, answer_id, account_id, action, flag_ids, note, created_on
+            from moderation_actions
+            order by created_on desc, id desc
+            limit $1 offset $2",
+        )
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .map(to_moderation_action)
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(actions) => Ok(actions),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn is_hidden(&self, target: &FlagTarget) -> Result<bool, Error> {
+        let (table, _, resource, id) = flag_target(target);
+
+        match sqlx::query(&format!(
+            "select hidden_at is not null as hidden from {} where id = $1",
+            table
+        ))
+        .bind(id)
+        .map(|row: PgRow| row.get::<bool, _>("hidden"))
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(hidden) => Ok(hidden),
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound { resource, id }),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl AccountRepository for PostgresStore {
+    async fn add_account(&self, account: Account) -> Result<Account, Error> {
+        match sqlx::query(
+            "insert into users (email, password) values ($1, $2)
+            returning id, email, password, role",
+        )
+        .bind(account.email)
+        .bind(account.password)
+        .map(to_account)
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(account) => Ok(account),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    async fn get_account(&self, email: &str) -> Result<Option<Account>, Error> {
+        match sqlx::query("select id, email, password, role from users where email = $1")
+            .bind(email)
+            .map(to_account)
+            .fetch_optional(&self.connection)
+            .await
+        {
+            Ok(account) => Ok(account),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(Error::from(e))
+            }
+        }
+    }
+}
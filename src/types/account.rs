@@ -0,0 +1,48 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Claims of a signed bearer token
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Session {
+    /// Expiration time as UNIX timestamp
+    pub exp: i64,
+    /// Issue time as UNIX timestamp
+    pub iat: i64,
+    pub account_id: AccountId,
+    pub role: Role,
+}
+
+impl Session {
+    pub fn is_moderator(&self) -> bool {
+        self.role == Role::Moderator
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Account {
+    pub id: Option<AccountId>,
+    pub email: String,
+    pub password: String,
+    /// Roles are granted by an administrator, never by the client
+    #[serde(default, skip_deserializing)]
+    pub role: Role,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    User,
+    /// May edit and delete content of every account
+    Moderator,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+pub struct AccountId(pub i32);
+
+impl fmt::Display for AccountId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "id: {}", self.0)
+    }
+}
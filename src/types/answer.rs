@@ -1,10 +1,12 @@
 use std::fmt;
 
+use super::account::AccountId;
 use super::question::QuestionId;
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
 pub struct AnswerId(pub i32);
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -12,6 +14,13 @@ pub struct Answer {
     pub id: AnswerId,
     pub content: String,
     pub question_id: QuestionId,
+    /// Author of the answer, `None` for answers given before accounts existed
+    pub account_id: Option<AccountId>,
+    pub created_on: DateTime<Utc>,
+    /// Time of the last edit, the creation time for unedited answers
+    pub updated_on: DateTime<Utc>,
+    /// Up votes minus down votes
+    pub score: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -20,6 +29,12 @@ pub struct NewAnswer {
     pub question_id: QuestionId,
 }
 
+/// Body of an answer update, only the content can be changed
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateAnswer {
+    pub content: String,
+}
+
 impl fmt::Display for AnswerId {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         write!(f, "id: {}", self.0)
@@ -0,0 +1,41 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::account::AccountId;
+use super::answer::AnswerId;
+use super::question::QuestionId;
+
+/// A short remark under a question or an answer, exactly one of
+/// `question_id` and `answer_id` is set
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Comment {
+    pub id: CommentId,
+    pub content: String,
+    pub question_id: Option<QuestionId>,
+    pub answer_id: Option<AnswerId>,
+    pub account_id: AccountId,
+    pub created_on: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewComment {
+    pub content: String,
+}
+
+/// What a comment is posted under
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommentTarget {
+    Question(QuestionId),
+    Answer(AnswerId),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+pub struct CommentId(pub i32);
+
+impl fmt::Display for CommentId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "id: {}", self.0)
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use handle_errors::{Error, FieldError};
+
+use super::pagination::Pagination;
+use super::tag::normalize_tag;
+
+/// How the requested tags have to match the tags of a question
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TagMatch {
+    /// The question carries at least one of the tags
+    #[default]
+    Any,
+    /// The question carries every one of the tags
+    All,
+}
+
+/// Order of the question list
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum QuestionSort {
+    #[default]
+    Newest,
+    Oldest,
+    MostAnswers,
+    Score,
+    Title,
+}
+
+impl QuestionSort {
+    /// Whether the order follows `created_on`, which keyset cursors depend on
+    pub fn is_chronological(&self) -> bool {
+        matches!(self, QuestionSort::Newest | QuestionSort::Oldest)
+    }
+}
+
+/// Filters and ordering for the question list,
+/// the default matches every question newest first
+#[derive(Debug, Default, Clone)]
+pub struct QuestionFilter {
+    pub tags: Vec<String>,
+    pub tag_match: TagMatch,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    /// `Some(true)` only keeps questions with answers, `Some(false)` only those without
+    pub answered: Option<bool>,
+    pub sort: QuestionSort,
+}
+
+/// Query parameters understood by `extract_question_filter`
+pub const QUESTION_FILTER_PARAMS: [&str; 6] = [
+    "tag",
+    "tag_match",
+    "created_after",
+    "created_before",
+    "answered",
+    "sort",
+];
+
+/// Reads the filter parameters of the query of the `/questions` route,
+/// synonyms among the tags are left for the store to resolve
+/// # Example query
+/// `/questions?tag=rust,warp&tag_match=all&answered=false&sort=oldest`
+///
+/// Dates are accepted as RFC 3339 timestamps or plain `YYYY-MM-DD` dates.
+pub fn extract_question_filter(
+    params: &HashMap<String, String>,
+) -> Result<QuestionFilter, Error> {
+    let mut filter = QuestionFilter::default();
+    let mut errors = Vec::new();
+
+    // Normalized like the tags of a question, so `Rust Lang` finds `rust-lang`,
+    // tags which cannot be normalized are looked up as given
+    if let Some(tags) = params.get("tag") {
+        filter.tags = tags
+            .split(',')
+            .map(|tag| tag.trim())
+            .filter(|tag| !tag.is_empty())
+            .map(|tag| normalize_tag(tag).unwrap_or_else(|| tag.to_string()))
+            .collect();
+    }
+
+    if let Some(tag_match) = params.get("tag_match") {
+        match tag_match.as_str() {
+            "any" => filter.tag_match = TagMatch::Any,
+            "all" => filter.tag_match = TagMatch::All,
+            _ => errors.push(FieldError::new("tag_match", "must be one of: any, all")),
+        }
+    }
+
+    for (key, target) in [
+        ("created_after", &mut filter.created_after),
+        ("created_before", &mut filter.created_before),
+    ] {
+        if let Some(value) = params.get(key) {
+            match parse_date(value) {
+                Some(date) => *target = Some(date),
+                None => errors.push(FieldError::new(
+                    key,
+                    "must be an RFC 3339 timestamp or a YYYY-MM-DD date",
+                )),
+            }
+        }
+    }
+
+    if let Some(answered) = params.get("answered") {
+        match answered.parse::<bool>() {
+            Ok(answered) => filter.answered = Some(answered),
+            Err(_) => errors.push(FieldError::new("answered", "must be true or false")),
+        }
+    }
+
+    if let Some(sort) = params.get("sort") {
+        match sort.as_str() {
+            "newest" => filter.sort = QuestionSort::Newest,
+            "oldest" => filter.sort = QuestionSort::Oldest,
+            "most_answers" => filter.sort = QuestionSort::MostAnswers,
+            "score" => filter.sort = QuestionSort::Score,
+            "title" => filter.sort = QuestionSort::Title,
+            _ => errors.push(FieldError::new(
+                "sort",
+                "must be one of: newest, oldest, most_answers, score, title",
+            )),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(Error::ValidationError(errors));
+    }
+
+    Ok(filter)
+}
+
+/// Keyset cursors can only walk the chronological orders
+pub fn check_cursor_sort(filter: &QuestionFilter, pagination: &Pagination) -> Result<(), Error> {
+    match pagination.cursor.is_some() && !filter.sort.is_chronological() {
+        true => Err(Error::ValidationError(vec![FieldError::new(
+            "sort",
+            "must be newest or oldest to page with a cursor",
+        )])),
+        false => Ok(()),
+    }
+}
+
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc())
+}
@@ -1,3 +1,11 @@
+pub mod account;
 pub mod answer;
+pub mod comment;
+pub mod filter;
+pub mod moderation;
 pub mod pagination;
 pub mod question;
+pub mod revision;
+pub mod search;
+pub mod tag;
+pub mod vote;
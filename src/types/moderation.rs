@@ -0,0 +1,153 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::account::AccountId;
+use super::answer::AnswerId;
+use super::question::QuestionId;
+
+/// Why a post was flagged
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum FlagReason {
+    Spam,
+    Offensive,
+    Duplicate,
+    OffTopic,
+}
+
+/// What a moderator did about the flags of a post
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationActionKind {
+    /// The flags were unfounded, hidden posts become visible again
+    Dismiss,
+    /// Deletes the post in a way only moderators can undo
+    Hide,
+    /// Deletes the post like its author would
+    Delete,
+}
+
+/// A question or an answer under moderation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlagTarget {
+    Question(QuestionId),
+    Answer(AnswerId),
+}
+
+/// A report of a post to the moderators, exactly one
+/// of `question_id` and `answer_id` is set
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Flag {
+    pub id: i32,
+    pub question_id: Option<QuestionId>,
+    pub answer_id: Option<AnswerId>,
+    pub account_id: Option<AccountId>,
+    pub reason: FlagReason,
+    pub details: Option<String>,
+    pub created_on: DateTime<Utc>,
+    /// Set once a moderator acted on the post
+    pub resolved_on: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewFlag {
+    pub reason: FlagReason,
+    pub details: Option<String>,
+}
+
+/// A post with open flags, as listed in the moderation queue
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueueEntry {
+    pub question_id: Option<QuestionId>,
+    pub answer_id: Option<AnswerId>,
+    /// Title of a flagged question, `None` for answers
+    pub title: Option<String>,
+    pub content: String,
+    pub hidden: bool,
+    pub flag_count: i64,
+    /// Every distinct reason given, in the order of `FlagReason`
+    pub reasons: Vec<FlagReason>,
+    pub first_flagged_on: DateTime<Utc>,
+    pub last_flagged_on: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewModerationAction {
+    pub action: ModerationActionKind,
+    pub note: Option<String>,
+}
+
+/// Entry of the moderation audit trail
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModerationAction {
+    pub id: i32,
+    pub question_id: Option<QuestionId>,
+    pub answer_id: Option<AnswerId>,
+    /// Moderator who acted
+    pub account_id: Option<AccountId>,
+    pub action: ModerationActionKind,
+    /// The open flags the action resolved
+    pub flag_ids: Vec<i32>,
+    pub note: Option<String>,
+    pub created_on: DateTime<Utc>,
+}
+
+impl FlagReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FlagReason::Spam => "spam",
+            FlagReason::Offensive => "offensive",
+            FlagReason::Duplicate => "duplicate",
+            FlagReason::OffTopic => "off-topic",
+        }
+    }
+}
+
+impl ModerationActionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationActionKind::Dismiss => "dismiss",
+            ModerationActionKind::Hide => "hide",
+            ModerationActionKind::Delete => "delete",
+        }
+    }
+}
+
+impl FromStr for FlagReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "spam" => Ok(FlagReason::Spam),
+            "offensive" => Ok(FlagReason::Offensive),
+            "duplicate" => Ok(FlagReason::Duplicate),
+            "off-topic" => Ok(FlagReason::OffTopic),
+            _ => Err(format!("unknown flag reason {}", s)),
+        }
+    }
+}
+
+impl FromStr for ModerationActionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dismiss" => Ok(ModerationActionKind::Dismiss),
+            "hide" => Ok(ModerationActionKind::Hide),
+            "delete" => Ok(ModerationActionKind::Delete),
+            _ => Err(format!("unknown moderation action {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for FlagTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            FlagTarget::Question(id) => write!(f, "question {}", id.0),
+            FlagTarget::Answer(id) => write!(f, "answer {}", id.0),
+        }
+    }
+}
@@ -1,15 +1,101 @@
 use std::collections::HashMap;
 
-use handle_errors::Error;
+use crate::config::PaginationConfig;
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use handle_errors::{Error, FieldError};
+
+/// Query parameters understood by `extract_pagination`
+pub const PAGINATION_PARAMS: [&str; 4] = ["limit", "offset", "after", "before"];
 
 /// Pagination struct which is getting extract
 /// from query params
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Pagination {
-    /// The index of the last item which has to be returned
-    pub limit: Option<u32>,
+    /// The number of items which have to be returned
+    pub limit: u32,
     /// The index of the first item which has to be returned
     pub offset: u32,
+    /// Keyset position to continue from, never combined with an offset
+    pub cursor: Option<Cursor>,
+}
+
+/// Where an item sits in a chronological listing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub created_on: DateTime<Utc>,
+    pub id: i32,
+}
+
+/// Continue a listing right after or right before a known item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cursor {
+    After(Position),
+    Before(Position),
+}
+
+/// One page of a listing together with what is needed to navigate it
+#[derive(Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Number of items in the whole listing
+    pub total: i64,
+    /// Position to continue after for the following page, if there is one
+    pub next: Option<Position>,
+    /// Position to continue before for the preceding page, if there is one
+    pub prev: Option<Position>,
+}
+
+impl Position {
+    /// Encodes the position as the opaque token used in `after=`/`before=`
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!(
+            "{}:{}",
+            self.created_on.timestamp_micros(),
+            self.id
+        ))
+    }
+
+    pub fn decode(token: &str) -> Option<Position> {
+        let decoded = String::from_utf8(URL_SAFE_NO_PAD.decode(token).ok()?).ok()?;
+        let (micros, id) = decoded.split_once(':')?;
+
+        Some(Position {
+            created_on: DateTime::from_timestamp_micros(micros.parse().ok()?)?,
+            id: id.parse().ok()?,
+        })
+    }
+}
+
+impl<T> Page<T> {
+    /// Builds a page from the rows a backend fetched for it: in listing order,
+    /// or in reverse order when paging backwards, with up to one extra row
+    /// beyond the limit to tell whether more items follow
+    pub fn from_rows(mut rows: Vec<(Position, T)>, total: i64, pagination: &Pagination) -> Page<T> {
+        let more = rows.len() > pagination.limit as usize;
+        rows.truncate(pagination.limit as usize);
+
+        if let Some(Cursor::Before(_)) = pagination.cursor {
+            rows.reverse();
+        }
+
+        let first = rows.first().map(|(position, _)| *position);
+        let last = rows.last().map(|(position, _)| *position);
+
+        let (next, prev) = match pagination.cursor {
+            None => (last.filter(|_| more), first.filter(|_| pagination.offset > 0)),
+            Some(Cursor::After(_)) => (last.filter(|_| more), first),
+            Some(Cursor::Before(_)) => (last, first.filter(|_| more)),
+        };
+
+        Page {
+            items: rows.into_iter().map(|(_, item)| item).collect(),
+            total,
+            next,
+            prev,
+        }
+    }
 }
 
 /// Extract query parameters from the `/questions` route
@@ -17,41 +103,211 @@ pub struct Pagination {
 /// GET requests to this route can have a pagination attached so we just
 /// return the questions we need
 /// `/questions?limit=1&offset=10`
+///
+/// Instead of an offset, `after` or `before` can carry the token of
+/// a `next`/`prev` link to page through the listing by keyset
+/// `/questions?limit=10&after=MTc0NjUyMTIwMDAwMDAwMDo0Mg`
+///
+/// Every parameter is optional, without `limit` a page holds
+/// `default_limit` items and no page holds more than `max_limit`.
 /// # Example usage
-/// ```rust
+/// ```ignore
 /// use std::collections::HashMap;
 ///
+/// let config = PaginationConfig { default_limit: 20, max_limit: 100 };
 /// let mut query = HashMap::new();
-/// query.insert("limit".to_string(), "1".to_string());
 /// query.insert("offset".to_string(), "10".to_string());
-/// let p = types::pagination::extract_pagination(query).unwrap();
-/// assert_eq!(p.limit, 1);
+/// let p = extract_pagination(&query, &config).unwrap();
+/// assert_eq!(p.limit, 20);
 /// assert_eq!(p.offset, 10);
 /// ```
-pub fn extract_pagination(params: HashMap<String, String>) -> Result<Pagination, Error> {
-    // Could be improved in the future
-    if params.contains_key("limit") && params.contains_key("offset") {
-        // Takes the "limit" parameter in the query
-        // and tries to convert it to a number
-        let limit = params
-            .get("limit")
-            .unwrap()
-            .parse::<u32>()
-            .map_err(Error::ParseError)?;
-
-        // Takes the "offset" parameter in the query
-        // and tries to convert it to a number
-        let offset = params
-            .get("offset")
-            .unwrap()
-            .parse::<u32>()
-            .map_err(Error::ParseError)?;
-
-        return Ok(Pagination {
-            limit: Some(limit),
-            offset,
-        });
-    }
-
-    Err(Error::MissingParameters)
+pub fn extract_pagination(
+    params: &HashMap<String, String>,
+    config: &PaginationConfig,
+) -> Result<Pagination, Error> {
+    let mut errors = Vec::new();
+
+    let limit = match params.get("limit").map(|limit| limit.parse::<u32>()) {
+        None => config.default_limit,
+        Some(Ok(limit)) if (1..=config.max_limit).contains(&limit) => limit,
+        Some(_) => {
+            errors.push(FieldError::new(
+                "limit",
+                &format!("must be a whole number between 1 and {}", config.max_limit),
+            ));
+            config.default_limit
+        }
+    };
+
+    let offset = match params.get("offset").map(|offset| offset.parse::<u32>()) {
+        None => 0,
+        Some(Ok(offset)) => offset,
+        Some(Err(_)) => {
+            errors.push(FieldError::new("offset", "must be a whole number of at least 0"));
+            0
+        }
+    };
+
+    let cursor = match (params.get("after"), params.get("before")) {
+        (Some(_), Some(_)) => {
+            errors.push(FieldError::new("before", "cannot be combined with `after`"));
+            None
+        }
+        (Some(token), None) => match Position::decode(token) {
+            Some(position) => Some(Cursor::After(position)),
+            None => {
+                errors.push(FieldError::new("after", "is not a valid cursor"));
+                None
+            }
+        },
+        (None, Some(token)) => match Position::decode(token) {
+            Some(position) => Some(Cursor::Before(position)),
+            None => {
+                errors.push(FieldError::new("before", "is not a valid cursor"));
+                None
+            }
+        },
+        (None, None) => None,
+    };
+
+    if params.contains_key("offset") && cursor.is_some() {
+        errors.push(FieldError::new("offset", "cannot be combined with a cursor"));
+    }
+
+    if !errors.is_empty() {
+        return Err(Error::ValidationError(errors));
+    }
+
+    Ok(Pagination {
+        limit,
+        offset,
+        cursor,
+    })
+}
+
+/// Rejects query parameters a route does not understand,
+/// instead of silently ignoring a typo like `limt=5`
+pub fn reject_unknown_params(
+    params: &HashMap<String, String>,
+    known: &[&str],
+) -> Result<(), Error> {
+    let mut unknown: Vec<&String> = params
+        .keys()
+        .filter(|key| !known.contains(&key.as_str()))
+        .collect();
+
+    if unknown.is_empty() {
+        return Ok(());
+    }
+
+    unknown.sort();
+
+    Err(Error::ValidationError(
+        unknown
+            .into_iter()
+            .map(|key| FieldError::new(key, "is not a supported parameter"))
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: PaginationConfig = PaginationConfig {
+        default_limit: 20,
+        max_limit: 100,
+    };
+
+    fn query(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn invalid_fields(result: Result<Pagination, Error>) -> Vec<String> {
+        match result {
+            Err(Error::ValidationError(errors)) => errors.into_iter().map(|e| e.field).collect(),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+    }
+
+    fn position() -> Position {
+        Position {
+            created_on: DateTime::from_timestamp_micros(1_746_521_200_123_456).unwrap(),
+            id: 42,
+        }
+    }
+
+    #[test]
+    fn extract_pagination_defaults() {
+        let pagination = extract_pagination(&query(&[]), &CONFIG).unwrap();
+
+        assert_eq!(pagination.limit, 20);
+        assert_eq!(pagination.offset, 0);
+        assert_eq!(pagination.cursor, None);
+    }
+
+    #[test]
+    fn extract_pagination_reads_limit_and_offset() {
+        let pagination =
+            extract_pagination(&query(&[("limit", "100"), ("offset", "10")]), &CONFIG).unwrap();
+
+        assert_eq!(pagination.limit, 100);
+        assert_eq!(pagination.offset, 10);
+    }
+
+    #[test]
+    fn extract_pagination_rejects_invalid_values() {
+        for limit in ["0", "101", "-1", "ten"] {
+            let result = extract_pagination(&query(&[("limit", limit)]), &CONFIG);
+            assert_eq!(invalid_fields(result), vec!["limit"]);
+        }
+
+        let result = extract_pagination(&query(&[("limit", "0"), ("offset", "-5")]), &CONFIG);
+        assert_eq!(invalid_fields(result), vec!["limit", "offset"]);
+    }
+
+    #[test]
+    fn extract_pagination_reads_cursors() {
+        let token = position().encode();
+
+        let after = extract_pagination(&query(&[("after", &token)]), &CONFIG).unwrap();
+        assert_eq!(after.cursor, Some(Cursor::After(position())));
+
+        let before = extract_pagination(&query(&[("before", &token)]), &CONFIG).unwrap();
+        assert_eq!(before.cursor, Some(Cursor::Before(position())));
+    }
+
+    #[test]
+    fn extract_pagination_rejects_conflicting_cursors() {
+        let token = position().encode();
+
+        let both = extract_pagination(&query(&[("after", &token), ("before", &token)]), &CONFIG);
+        assert_eq!(invalid_fields(both), vec!["before"]);
+
+        let offset = extract_pagination(&query(&[("after", &token), ("offset", "1")]), &CONFIG);
+        assert_eq!(invalid_fields(offset), vec!["offset"]);
+
+        let broken = extract_pagination(&query(&[("after", "not a cursor")]), &CONFIG);
+        assert_eq!(invalid_fields(broken), vec!["after"]);
+    }
+
+    #[test]
+    fn position_round_trips() {
+        let token = position().encode();
+
+        assert!(token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(Position::decode(&token), Some(position()));
+    }
+
+    #[test]
+    fn position_decode_rejects_garbage() {
+        assert_eq!(Position::decode(""), None);
+        assert_eq!(Position::decode("!!!"), None);
+        assert_eq!(Position::decode(&URL_SAFE_NO_PAD.encode("42")), None);
+        assert_eq!(Position::decode(&URL_SAFE_NO_PAD.encode("x:42")), None);
+        assert_eq!(Position::decode(&URL_SAFE_NO_PAD.encode("1:x")), None);
+    }
 }
@@ -1,13 +1,60 @@
 use std::fmt;
 
+use chrono::{DateTime, Utc};
+use handle_errors::{Error, FieldError};
 use serde::{Deserialize, Serialize};
 
+use super::account::AccountId;
+use super::answer::{Answer, AnswerId};
+
+/// Longest question title accepted, in characters
+pub const MAX_TITLE_LENGTH: usize = 150;
+
+/// Longest question or answer accepted, in characters
+pub const MAX_CONTENT_LENGTH: usize = 30_000;
+
+/// Most lines a question or answer may have, every revision
+/// is diffed line by line against the one before it
+pub const MAX_CONTENT_LINES: usize = 1_000;
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Question {
     pub id: QuestionId,
     pub title: String,
     pub content: String,
     pub tags: Option<Vec<String>>,
+    /// Author of the question, `None` for questions asked before accounts existed
+    pub account_id: Option<AccountId>,
+    #[serde(default)]
+    pub created_on: DateTime<Utc>,
+    /// Time of the last edit, the creation time for unedited questions
+    #[serde(default)]
+    pub updated_on: DateTime<Utc>,
+    /// Up votes minus down votes
+    #[serde(default)]
+    pub score: i32,
+    /// The answer the author marked as solving the question
+    #[serde(default)]
+    pub accepted_answer_id: Option<AnswerId>,
+}
+
+/// A question as it appears in the question list
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuestionSummary {
+    #[serde(flatten)]
+    pub question: Question,
+    pub answer_count: i64,
+}
+
+/// A question together with all of its answers, the accepted one
+/// first and the rest oldest first,
+/// for rendering a question page in one request
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuestionWithAnswers {
+    #[serde(flatten)]
+    pub question: Question,
+    pub answer_count: i64,
+    pub answers: Vec<Answer>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -17,9 +64,39 @@ pub struct NewQuestion {
     pub tags: Option<Vec<String>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Default)]
 pub struct QuestionId(pub i32);
 
+/// Checks the size of a question, or of an answer without a title,
+/// before it is stored
+pub fn check_post_size(title: Option<&str>, content: &str) -> Result<(), Error> {
+    let mut errors = Vec::new();
+
+    if title.is_some_and(|title| title.chars().count() > MAX_TITLE_LENGTH) {
+        errors.push(FieldError::new(
+            "title",
+            &format!("must be at most {} characters long", MAX_TITLE_LENGTH),
+        ));
+    }
+
+    if content.chars().count() > MAX_CONTENT_LENGTH {
+        errors.push(FieldError::new(
+            "content",
+            &format!("must be at most {} characters long", MAX_CONTENT_LENGTH),
+        ));
+    } else if content.lines().count() > MAX_CONTENT_LINES {
+        errors.push(FieldError::new(
+            "content",
+            &format!("must have at most {} lines", MAX_CONTENT_LINES),
+        ));
+    }
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(Error::ValidationError(errors)),
+    }
+}
+
 impl fmt::Display for Question {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         write!(
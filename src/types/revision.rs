@@ -0,0 +1,204 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use similar::{Algorithm, DiffTag};
+
+use std::time::{Duration, Instant};
+
+use super::account::AccountId;
+use super::answer::AnswerId;
+use super::question::QuestionId;
+
+/// A stored version of a question, revision 1 is the question as it was asked
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuestionRevision {
+    pub question_id: QuestionId,
+    pub revision: i32,
+    pub title: String,
+    pub content: String,
+    pub tags: Option<Vec<String>>,
+    /// Who wrote this version
+    pub account_id: Option<AccountId>,
+    pub created_on: DateTime<Utc>,
+}
+
+/// A stored version of an answer, revision 1 is the answer as it was given
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnswerRevision {
+    pub answer_id: AnswerId,
+    pub revision: i32,
+    pub content: String,
+    /// Who wrote this version
+    pub account_id: Option<AccountId>,
+    pub created_on: DateTime<Utc>,
+}
+
+/// A question revision with what changed since the one before it
+#[derive(Debug, Serialize, Clone)]
+pub struct QuestionRevisionDiff {
+    #[serde(flatten)]
+    pub revision: QuestionRevision,
+    pub title_diff: Vec<DiffLine>,
+    pub content_diff: Vec<DiffLine>,
+    pub tags_added: Vec<String>,
+    pub tags_removed: Vec<String>,
+}
+
+/// An answer revision with what changed since the one before it
+#[derive(Debug, Serialize, Clone)]
+pub struct AnswerRevisionDiff {
+    #[serde(flatten)]
+    pub revision: AnswerRevision,
+    pub content_diff: Vec<DiffLine>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+/// One line of a line-by-line diff
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub line: String,
+}
+
+/// Pairs every revision with its changes, the first one
+/// is compared against an empty question
+pub fn question_diffs(revisions: Vec<QuestionRevision>) -> Vec<QuestionRevisionDiff> {
+    let mut previous: Option<QuestionRevision> = None;
+    let mut diffs = Vec::with_capacity(revisions.len());
+
+    for revision in revisions {
+        let (title, content, tags) = match previous {
+            Some(ref p) => (p.title.as_str(), p.content.as_str(), p.tags.clone()),
+            None => ("", "", None),
+        };
+        let old_tags = tags.unwrap_or_default();
+        let new_tags = revision.tags.clone().unwrap_or_default();
+
+        diffs.push(QuestionRevisionDiff {
+            title_diff: diff_lines(title, &revision.title),
+            content_diff: diff_lines(content, &revision.content),
+            tags_added: new_tags.iter().filter(|t| !old_tags.contains(t)).cloned().collect(),
+            tags_removed: old_tags.iter().filter(|t| !new_tags.contains(t)).cloned().collect(),
+            revision: revision.clone(),
+        });
+        previous = Some(revision);
+    }
+
+    diffs
+}
+
+/// Pairs every revision with its changes, the first one
+/// is compared against an empty answer
+pub fn answer_diffs(revisions: Vec<AnswerRevision>) -> Vec<AnswerRevisionDiff> {
+    let mut previous = String::new();
+    let mut diffs = Vec::with_capacity(revisions.len());
+
+    for revision in revisions {
+        let content_diff = diff_lines(&previous, &revision.content);
+        previous = revision.content.clone();
+        diffs.push(AnswerRevisionDiff {
+            revision,
+            content_diff,
+        });
+    }
+
+    diffs
+}
+
+/// Time a single diff may take, slower ones fall back to a coarser diff
+const DIFF_DEADLINE: Duration = Duration::from_millis(50);
+
+/// Line-by-line diff of two texts, using Myers' algorithm
+/// which needs memory linear in the number of lines
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    let ops = similar::capture_diff_slices_deadline(
+        Algorithm::Myers,
+        &old,
+        &new,
+        Some(Instant::now() + DIFF_DEADLINE),
+    );
+
+    let mut diff = Vec::new();
+    let mut push = |kind: DiffKind, lines: &[&str]| {
+        diff.extend(lines.iter().map(|line| DiffLine {
+            kind,
+            line: line.to_string(),
+        }))
+    };
+
+    for op in ops {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        match tag {
+            DiffTag::Equal => push(DiffKind::Unchanged, &old[old_range]),
+            DiffTag::Delete => push(DiffKind::Removed, &old[old_range]),
+            DiffTag::Insert => push(DiffKind::Added, &new[new_range]),
+            DiffTag::Replace => {
+                push(DiffKind::Removed, &old[old_range]);
+                push(DiffKind::Added, &new[new_range]);
+            }
+        }
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(kind: DiffKind, line: &str) -> DiffLine {
+        DiffLine {
+            kind,
+            line: line.to_string(),
+        }
+    }
+
+    #[test]
+    fn diff_lines_marks_changes() {
+        let diff = diff_lines("one\ntwo\nthree", "one\n2\nthree\nfour");
+
+        assert_eq!(
+            diff,
+            vec![
+                line(DiffKind::Unchanged, "one"),
+                line(DiffKind::Removed, "two"),
+                line(DiffKind::Added, "2"),
+                line(DiffKind::Unchanged, "three"),
+                line(DiffKind::Added, "four"),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_lines_of_empty_text() {
+        assert_eq!(diff_lines("", ""), vec![]);
+        assert_eq!(diff_lines("", "new"), vec![line(DiffKind::Added, "new")]);
+        assert_eq!(diff_lines("old", ""), vec![line(DiffKind::Removed, "old")]);
+    }
+
+    #[test]
+    fn diff_lines_handles_large_texts() {
+        let old: String = (0..1_000).map(|i| format!("{}\n", i)).collect();
+        let new: String = (0..1_000).map(|i| format!("{}\n", i * 7 % 1_000)).collect();
+
+        let diff = diff_lines(&old, &new);
+
+        let kept = |kind: DiffKind| {
+            diff.iter()
+                .filter(|l| l.kind != kind)
+                .map(|l| format!("{}\n", l.line))
+                .collect::<String>()
+        };
+        assert_eq!(kept(DiffKind::Added), old);
+        assert_eq!(kept(DiffKind::Removed), new);
+    }
+}
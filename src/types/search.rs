@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+use super::question::Question;
+
+/// A question matching a full-text search
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchResult {
+    pub question: Question,
+    /// Relevance of the match, higher is better
+    pub rank: f32,
+    /// HTML excerpt of the question and its answers with the user text
+    /// escaped and the matched words wrapped in `<mark>` tags
+    pub snippet: String,
+}
+
+/// Control characters the database puts around matched words,
+/// they are stripped from the searched text so users cannot fake them
+pub const MATCH_START: char = '\u{2}';
+pub const MATCH_END: char = '\u{3}';
+
+/// Escapes the characters with a meaning in HTML text and attributes
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Turns a snippet with matches between `MATCH_START` and `MATCH_END`
+/// into escaped HTML with the matches wrapped in `<mark>` tags
+pub fn mark_matches(snippet: &str) -> String {
+    escape_html(snippet)
+        .replace(MATCH_START, "<mark>")
+        .replace(MATCH_END, "</mark>")
+}
@@ -0,0 +1,166 @@
+use chrono::{DateTime, Utc};
+use handle_errors::{Error, FieldError};
+use serde::{Deserialize, Serialize};
+
+use super::account::AccountId;
+use super::question::QuestionId;
+
+/// Questions carrying more tags than this are rejected
+pub const MAX_TAGS_PER_QUESTION: usize = 5;
+
+/// Longest tag name accepted after normalization
+const MAX_TAG_LENGTH: usize = 35;
+
+/// A tag with the number of questions carrying it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Tag {
+    pub name: String,
+    pub description: Option<String>,
+    /// Counts questions which are not deleted
+    pub question_count: i64,
+}
+
+/// Body of a tag update, only the description can be changed
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateTag {
+    pub description: Option<String>,
+}
+
+/// Another name for a tag, replaced by the tag whenever a question is written
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TagSynonym {
+    pub alias: String,
+    /// Name of the canonical tag
+    pub tag: String,
+    /// Moderator who added the synonym
+    pub account_id: Option<AccountId>,
+    pub created_on: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewTagSynonym {
+    pub alias: String,
+}
+
+/// Body of a merge, the tag in the path is merged into `into`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewTagMerge {
+    pub into: String,
+}
+
+/// Record of a merge of one tag into another
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TagMerge {
+    pub id: i32,
+    pub source: String,
+    pub target: String,
+    /// Moderator who merged the tags
+    pub account_id: Option<AccountId>,
+    /// Questions which carried the source tag, deleted ones included
+    pub question_ids: Vec<QuestionId>,
+    pub created_on: DateTime<Utc>,
+}
+
+/// Turns a tag into its canonical slug: lowercase, whitespace and
+/// underscores become dashes and runs of dashes are collapsed.
+/// Returns `None` if anything but letters, digits, `+`, `.` or `-` remains.
+/// # Example
+/// `"Async Await"` becomes `"async-await"`, `"C++"` becomes `"c++"`
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let mut slug = String::with_capacity(tag.len());
+
+    for c in tag.trim().to_lowercase().chars() {
+        match c {
+            'a'..='z' | '0'..='9' | '+' | '.' => slug.push(c),
+            '-' | '_' => slug.push('-'),
+            c if c.is_whitespace() => slug.push('-'),
+            _ => return None,
+        }
+        if slug.ends_with("--") {
+            slug.pop();
+        }
+    }
+
+    let slug = slug.trim_matches('-');
+    match slug.is_empty() {
+        true => None,
+        false => Some(slug.to_string()),
+    }
+}
+
+/// Normalizes the tags of a new or edited question, dropping duplicates
+/// and keeping the order in which they were given
+pub fn normalize_tags(tags: Option<Vec<String>>) -> Result<Option<Vec<String>>, Error> {
+    let Some(tags) = tags else {
+        return Ok(None);
+    };
+
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    let mut errors = Vec::new();
+
+    for tag in tags {
+        match normalize_tag(&tag) {
+            Some(slug) if slug.chars().count() > MAX_TAG_LENGTH => errors.push(FieldError::new(
+                "tags",
+                &format!("{:?} is longer than {} characters", tag, MAX_TAG_LENGTH),
+            )),
+            Some(slug) => {
+                if !normalized.contains(&slug) {
+                    normalized.push(slug);
+                }
+            }
+            None => errors.push(FieldError::new(
+                "tags",
+                &format!("{:?} may only contain letters, digits, spaces and + . - _", tag),
+            )),
+        }
+    }
+
+    if normalized.len() > MAX_TAGS_PER_QUESTION {
+        errors.push(FieldError::new(
+            "tags",
+            &format!("at most {} tags are allowed", MAX_TAGS_PER_QUESTION),
+        ));
+    }
+
+    if !errors.is_empty() {
+        return Err(Error::ValidationError(errors));
+    }
+
+    Ok(Some(normalized))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_tag_builds_slugs() {
+        assert_eq!(normalize_tag("Async Await"), Some("async-await".to_string()));
+        assert_eq!(normalize_tag("C++"), Some("c++".to_string()));
+        assert_eq!(normalize_tag(" node.js "), Some("node.js".to_string()));
+        assert_eq!(normalize_tag("rust__lang"), Some("rust-lang".to_string()));
+        assert_eq!(normalize_tag("--web -  dev--"), Some("web-dev".to_string()));
+    }
+
+    #[test]
+    fn normalize_tag_rejects_other_characters() {
+        assert_eq!(normalize_tag("c#"), None);
+        assert_eq!(normalize_tag("<script>"), None);
+        assert_eq!(normalize_tag("   "), None);
+        assert_eq!(normalize_tag("---"), None);
+    }
+
+    #[test]
+    fn normalize_tags_drops_duplicates_and_caps_the_count() {
+        let tags = |tags: &[&str]| Some(tags.iter().map(|t| t.to_string()).collect());
+
+        assert_eq!(
+            normalize_tags(tags(&["Rust", "async await", "rust", "Async-Await"])).unwrap(),
+            Some(vec!["rust".to_string(), "async-await".to_string()])
+        );
+        assert!(normalize_tags(tags(&["a", "b", "c", "d", "e", "f"])).is_err());
+        assert!(normalize_tags(tags(&[&"x".repeat(36)])).is_err());
+        assert_eq!(normalize_tags(None).unwrap(), None);
+    }
+}
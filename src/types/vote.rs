@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// Direction of a vote on a question or an answer
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Vote {
+    Up,
+    Down,
+}
+
+/// Body of a vote, voting again replaces the earlier vote
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewVote {
+    pub vote: Vote,
+}
+
+impl Vote {
+    /// What the vote adds to the score of a post
+    pub fn value(&self) -> i16 {
+        match self {
+            Vote::Up => 1,
+            Vote::Down => -1,
+        }
+    }
+}